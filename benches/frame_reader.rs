@@ -0,0 +1,75 @@
+//! The frame update of a 4-player 64x64 game, read with `Line::read`, which splits every line
+//! into owned tokens, and with `FrameReader`. Run with `cargo bench`.
+
+#[macro_use]
+extern crate bencher;
+
+use std::fmt::Write;
+use bencher::{black_box, Bencher};
+use archon::halite::input::{FrameReader, Line};
+
+/// 4 players with 60 ships and 3 dropoffs each, and 800 changed cells.
+fn frame() -> String {
+    let mut frame = String::from("250\n");
+    for player_id in 0..4 {
+        writeln!(frame, "{} 60 3 123456", player_id).unwrap();
+        for ship in 0..60 {
+            writeln!(frame, "{} {} {} {}", player_id * 100 + ship, ship, player_id * 16, ship * 16).unwrap();
+        }
+        for dropoff in 0..3 {
+            writeln!(frame, "{} {} {}", player_id * 10 + dropoff, dropoff * 20, player_id * 16 + 8).unwrap();
+        }
+    }
+    writeln!(frame, "800").unwrap();
+    for cell in 0..800 {
+        writeln!(frame, "{} {} {}", cell % 64, cell / 64, cell * 7 % 1000).unwrap();
+    }
+    frame
+}
+
+fn lines_and_tokens(bench: &mut Bencher) {
+    let frame = frame();
+    bench.iter(|| {
+        let mut input = frame.as_bytes();
+        let mut line_number = 0;
+        let mut sum = 0;
+        while let Ok(line) = Line::read(&mut input, &mut line_number) {
+            for index in 0..line.tokens.len() {
+                sum += line.integer(index).unwrap();
+            }
+        }
+        black_box(sum)
+    });
+}
+
+fn frame_reader(bench: &mut Bencher) {
+    let frame = frame();
+    let mut frames = FrameReader::new(0);
+    bench.iter(|| {
+        let mut input = frame.as_bytes();
+        let mut sum = 0;
+        let [turn] = frames.read(&mut input).unwrap();
+        sum += turn;
+        for _ in 0..4 {
+            let [_, ships, dropoffs, halite] = frames.read(&mut input).unwrap();
+            sum += halite;
+            for _ in 0..ships {
+                let [ship_id, x, y, cargo] = frames.read(&mut input).unwrap();
+                sum += ship_id + x + y + cargo;
+            }
+            for _ in 0..dropoffs {
+                let [structure_id, x, y] = frames.read(&mut input).unwrap();
+                sum += structure_id + x + y;
+            }
+        }
+        let [cells] = frames.read(&mut input).unwrap();
+        for _ in 0..cells {
+            let [x, y, halite] = frames.read(&mut input).unwrap();
+            sum += x + y + halite;
+        }
+        black_box(sum)
+    });
+}
+
+benchmark_group!(benches, lines_and_tokens, frame_reader);
+benchmark_main!(benches);
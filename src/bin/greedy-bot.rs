@@ -0,0 +1,15 @@
+//! Mines the cell it is on until it runs low, then moves to the richest neighbor, and brings the
+//! cargo home the cheapest way. `--flog <file>` saves notes on every ship's mission for the
+//! Fluorine replay viewer.
+
+use std::env;
+use archon::halite::bot::run_bot;
+use archon::halite::bots::GreedyBot;
+
+fn main() {
+    let flog = env::args().skip_while(|arg| arg != "--flog").nth(1);
+    match flog {
+        Some(path) => run_bot(GreedyBot::new().with_flog(path)),
+        None => run_bot(GreedyBot::new()),
+    }
+}
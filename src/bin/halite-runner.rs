@@ -0,0 +1,298 @@
+//! Plays one game between bot executables with the in-crate engine, taking the same kind of
+//! flags `run_game.sh` passes to the official `halite` binary:
+//!
+//!     halite-runner --replay-directory replays/ --width 32 --height 32 "./bot_a" "./bot_b"
+//!
+//! or a whole tournament between bot versions:
+//!
+//!     halite-runner tournament --games 20 --jobs 4 --csv summary.csv "old=./bot_v1" "new=./bot_v2"
+
+use archon::halite::engine::process::{play_process_game, ProcessGame, ProcessOptions};
+use archon::halite::engine::tournament::{self, BotVersion, TournamentConfig};
+use archon::halite::engine::GameConfig;
+use serde_json::{json, Map, Value};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "\
+usage: halite-runner [options] <bot command>...
+
+options:
+  --width <n>                  map width (default 32)
+  --height <n>                 map height (default 32)
+  -s, --seed <n>               map seed (default: current time)
+  -n, --players <n>            number of players, 1, 2 or 4 (default: number of bot commands)
+  -i, --replay-directory <dir> where to write the replay (default replays/)
+  --no-replay                  do not write a replay
+  --turn-timeout <ms>          time a bot gets per turn (default 2000)
+  --init-timeout <ms>          time a bot gets to initialize (default 30000)
+  --no-timeout                 wait for bots forever
+  --results-as-json            print the results as JSON
+  -v, -vv, -vvv                the official engine's verbosity flags, accepted so its command
+                               lines work here too, and ignored
+
+       halite-runner tournament [options] <bot command>...
+
+options:
+  --games <n>                  games per pairing, map size and player count (default 10)
+  --sizes <n,...>              map sizes (default 32,40,48,56,64)
+  --players <n,...>            player counts (default 2,4)
+  -s, --seed <n>               seed of the first game, later games count up (default 0)
+  -j, --jobs <n>               games played at the same time (default 1)
+  --csv <file>                 write the summary as CSV
+  --json <file>                write the summary and every game as JSON
+  --bot <label>=<command>      a bot with a label of its own instead of its command
+  --turn-timeout, --init-timeout, --no-timeout as above";
+
+struct Arguments {
+    width: u32,
+    height: u32,
+    seed: u64,
+    players: Option<u32>,
+    replay_directory: Option<PathBuf>,
+    options: ProcessOptions,
+    results_as_json: bool,
+    bots: Vec<String>,
+}
+
+fn number<T: std::str::FromStr>(name: &str, value: String) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} expects a number, got {:?}", name, value))
+}
+
+fn numbers<T: std::str::FromStr>(name: &str, value: String) -> Result<Vec<T>, String> {
+    value.split(',').map(|item| number(name, item.trim().to_string())).collect()
+}
+
+fn parse_arguments(args: &[String]) -> Result<Arguments, String> {
+    let mut arguments = Arguments {
+        width: 32,
+        height: 32,
+        seed: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        players: None,
+        replay_directory: Some(PathBuf::from("replays/")),
+        options: ProcessOptions::default(),
+        results_as_json: false,
+        bots: Vec::new(),
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| -> Result<String, String> {
+            iter.next().cloned().ok_or_else(|| format!("{} expects a value", name))
+        };
+        match arg.as_str() {
+            "--width" => arguments.width = number(arg, value(arg)?)?,
+            "--height" => arguments.height = number(arg, value(arg)?)?,
+            "-s" | "--seed" => arguments.seed = number(arg, value(arg)?)?,
+            "-n" | "--players" => arguments.players = Some(number(arg, value(arg)?)?),
+            "-i" | "--replay-directory" => arguments.replay_directory = Some(PathBuf::from(value(arg)?)),
+            "--no-replay" => arguments.replay_directory = None,
+            "--turn-timeout" => arguments.options.turn_timeout =
+                Some(Duration::from_millis(number(arg, value(arg)?)?)),
+            "--init-timeout" => arguments.options.init_timeout =
+                Some(Duration::from_millis(number(arg, value(arg)?)?)),
+            "--no-timeout" => {
+                arguments.options.turn_timeout = None;
+                arguments.options.init_timeout = None;
+            }
+            "--results-as-json" => arguments.results_as_json = true,
+            "-h" | "--help" => return Err(String::new()),
+            // the official engine's verbosity; the runner has no log levels, so USAGE lists it as ignored
+            flag if flag.starts_with("-v") && flag.chars().skip(1).all(|c| c == 'v') => {}
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            bot => arguments.bots.push(bot.to_string()),
+        }
+    }
+    if arguments.bots.is_empty() {
+        return Err("no bot commands given".to_string());
+    }
+    Ok(arguments)
+}
+
+fn write_replay(directory: &Path, game: &ProcessGame) -> Result<PathBuf, String> {
+    fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let path = directory.join(format!("replay-{}-{}-{}-{}.hlt", timestamp, game.config.seed,
+                                      game.config.map_size.0, game.config.map_size.1));
+    game.replay.write_file(&path).map_err(|error| error.to_string())?;
+    Ok(path)
+}
+
+struct TournamentArguments {
+    config: TournamentConfig,
+    csv: Option<PathBuf>,
+    json: Option<PathBuf>,
+    bots: Vec<BotVersion>,
+}
+
+fn parse_tournament_arguments(args: &[String]) -> Result<TournamentArguments, String> {
+    let mut arguments = TournamentArguments {
+        config: TournamentConfig::default(),
+        csv: None,
+        json: None,
+        bots: Vec::new(),
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| -> Result<String, String> {
+            iter.next().cloned().ok_or_else(|| format!("{} expects a value", name))
+        };
+        let config = &mut arguments.config;
+        match arg.as_str() {
+            "--games" => config.games_per_pairing = number(arg, value(arg)?)?,
+            "--sizes" => config.map_sizes = numbers(arg, value(arg)?)?,
+            "--players" => config.player_counts = numbers(arg, value(arg)?)?,
+            "-s" | "--seed" => config.base_seed = number(arg, value(arg)?)?,
+            "-j" | "--jobs" => config.jobs = number(arg, value(arg)?)?,
+            "--csv" => arguments.csv = Some(PathBuf::from(value(arg)?)),
+            "--json" => arguments.json = Some(PathBuf::from(value(arg)?)),
+            "--bot" => arguments.bots.push(BotVersion::labeled(&value(arg)?)?),
+            "--turn-timeout" => config.options.turn_timeout =
+                Some(Duration::from_millis(number(arg, value(arg)?)?)),
+            "--init-timeout" => config.options.init_timeout =
+                Some(Duration::from_millis(number(arg, value(arg)?)?)),
+            "--no-timeout" => {
+                config.options.turn_timeout = None;
+                config.options.init_timeout = None;
+            }
+            "-h" | "--help" => return Err(String::new()),
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            bot => arguments.bots.push(BotVersion::new(bot)),
+        }
+    }
+    if arguments.bots.len() < 2 {
+        return Err("a tournament needs at least two bots".to_string());
+    }
+    for &players in arguments.config.player_counts.iter() {
+        for &size in arguments.config.map_sizes.iter() {
+            GameConfig::new((size, size), players, 0).validate()?;
+        }
+    }
+    Ok(arguments)
+}
+
+fn run_tournament(args: &[String]) {
+    let arguments = match parse_tournament_arguments(args) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            if !error.is_empty() {
+                eprintln!("{}\n", error);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let total = tournament::schedule(&arguments.config, arguments.bots.len()).len();
+    let mut finished = 0;
+    let results = tournament::run_tournament(&arguments.config, &arguments.bots, |result| {
+        finished += 1;
+        let winner = result.game.seats[result.players.iter()
+                                              .position(|player| player.rank == 1)
+                                              .unwrap_or(0)];
+        eprintln!("[{}/{}] seed {} on {}x{}: {} won",
+                  finished, total, result.game.seed, result.game.map_size, result.game.map_size,
+                  arguments.bots[winner].label);
+    });
+    let summaries = tournament::summarize(&arguments.bots, &results);
+    
+    println!("{:<24} {:>6} {:>6} {:>8} {:>10} {:>9} {:>8}",
+             "bot", "games", "wins", "win rate", "mean score", "mean rank", "rating");
+    for summary in summaries.iter() {
+        println!("{:<24} {:>6} {:>6} {:>7.1}% {:>10.1} {:>9.2} {:>8.1}",
+                 summary.label, summary.games, summary.wins, summary.win_rate() * 100.0,
+                 summary.mean_score, summary.mean_rank, summary.rating);
+    }
+    
+    if let Some(path) = arguments.csv {
+        if let Err(error) = fs::write(&path, tournament::summary_csv(&summaries)) {
+            eprintln!("could not write {}: {}", path.display(), error);
+        }
+    }
+    if let Some(path) = arguments.json {
+        let json = tournament::summary_json(&arguments.bots, &summaries, &results);
+        if let Err(error) = fs::write(&path, json.to_string()) {
+            eprintln!("could not write {}: {}", path.display(), error);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("tournament") {
+        run_tournament(&args[1..]);
+        return;
+    }
+    let arguments = match parse_arguments(&args) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            if !error.is_empty() {
+                eprintln!("{}\n", error);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    
+    let num_players = arguments.players.unwrap_or(arguments.bots.len() as u32);
+    // a single command plays against itself, like the official engine allows
+    let bots: Vec<String> = (0..num_players as usize)
+        .map(|player| arguments.bots[player % arguments.bots.len()].clone())
+        .collect();
+    let config = GameConfig::new((arguments.width, arguments.height), num_players, arguments.seed);
+    if let Err(error) = config.validate() {
+        eprintln!("{}", error);
+        process::exit(2);
+    }
+    
+    let game = match play_process_game(&config, &bots, &arguments.options) {
+        Ok(game) => game,
+        Err(error) => {
+            eprintln!("could not start bots: {}", error);
+            process::exit(1);
+        }
+    };
+    
+    let replay = match arguments.replay_directory {
+        Some(ref directory) => match write_replay(directory, &game) {
+            Ok(path) => Some(path),
+            Err(error) => {
+                eprintln!("could not write replay: {}", error);
+                None
+            }
+        },
+        None => None,
+    };
+    
+    if arguments.results_as_json {
+        let mut stats = Map::new();
+        let mut terminated = Map::new();
+        for player in game.result.players.iter() {
+            stats.insert(player.player_id.to_string(),
+                         json!({ "rank": player.rank, "score": player.halite }));
+            terminated.insert(player.player_id.to_string(), Value::Bool(player.error.is_some()));
+        }
+        println!("{}", json!({
+            "map_width": config.map_size.0,
+            "map_height": config.map_size.1,
+            "map_seed": config.seed,
+            "turns": game.result.turns,
+            "replay": replay.map(|path| path.display().to_string()),
+            "stats": stats,
+            "terminated": terminated,
+        }));
+    } else {
+        println!("Map seed was {}, {}x{}, {} turns played",
+                 config.seed, config.map_size.0, config.map_size.1, game.result.turns);
+        if let Some(path) = replay {
+            println!("Opening a file at {}", path.display());
+        }
+        for player in game.result.players.iter() {
+            println!("Player {}, '{}', was rank {} with {} halite{}",
+                     player.player_id, game.result.bot_names[player.player_id as usize],
+                     player.rank, player.halite,
+                     player.error.as_ref().map(|error| format!(" ({})", error)).unwrap_or_default());
+        }
+    }
+}
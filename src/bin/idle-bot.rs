@@ -0,0 +1,8 @@
+//! Does nothing at all, keeping the halite it starts with.
+
+use archon::halite::bot::run_bot;
+use archon::halite::bots::IdleBot;
+
+fn main() {
+    run_bot(IdleBot);
+}
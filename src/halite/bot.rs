@@ -0,0 +1,205 @@
+//! Everything a bot binary has in common: reading the game, the turn loop and its bookkeeping.
+//! A bot only decides what to do each turn, its `main` hands it to `run_bot`.
+
+use std::env;
+use std::io::{stdin, stdout, BufRead, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use rand::{SeedableRng, XorShiftRng};
+use super::command::TurnCommands;
+use super::error::ProtocolError;
+use super::game::Khala;
+use super::log::{Log, LogConfig};
+use super::transcript::{replay_session, RecordedSession, TeeReader, TeeWriter, Transcript};
+use super::update::Update;
+
+pub trait Bot {
+    /// Sent to the engine once the bot is ready, e.g. `bot_1` for player 1.
+    fn name(&self) -> String;
+    
+    /// Called once with the initial game state, before the name is sent.
+    fn init<R, W>(&mut self, _khala: &Khala<R, W>) {}
+    
+    fn play_turn<R, W>(&mut self, khala: &Khala<R, W>) -> TurnCommands;
+    
+    /// Called once the game is over with the last state, e.g. to save what the bot recorded.
+    fn finish<R, W>(&mut self, _khala: &Khala<R, W>) {}
+}
+
+/// Plays a whole game on stdin and stdout, exiting with an error message on stderr when the
+/// engine sends something unexpected. Logging is set up from the environment and the command
+/// line, see `LogConfig`. With `--transcript <file>` the session is captured into a transcript;
+/// with `--replay-transcript <file>` a captured one is played again instead, printing the turns
+/// the bot now answers differently.
+pub fn run_bot<B: Bot>(mut bot: B) {
+    let (log_config, session, _) = parse_args();
+    match session {
+        Session::Live => {
+            if let Err(message) = run(&mut bot, Khala::read_from_stdin_with_log(&log_config)) {
+                fail(message);
+            }
+        }
+        Session::Capture(path) => {
+            let transcript = Transcript::create(&path).unwrap_or_else(|error| {
+                fail(format!("failed to create the transcript {}: {}", path.display(), error))
+            });
+            let reader = TeeReader::new(stdin().lock(), transcript.clone());
+            let writer = TeeWriter::new(stdout(), transcript);
+            if let Err(message) = run(&mut bot, Khala::read_with_log(reader, writer, &log_config)) {
+                fail(message);
+            }
+        }
+        Session::Replay(path) => {
+            let session = RecordedSession::load(&path).unwrap_or_else(|error| {
+                fail(format!("failed to read the transcript {}: {}", path.display(), error))
+            });
+            match replay_session(&mut bot, &session, &log_config) {
+                Ok(ref diffs) if diffs.is_empty() => {
+                    println!("all {} turns match", session.bot_lines.len().saturating_sub(1));
+                }
+                Ok(diffs) => {
+                    diffs.iter().for_each(|diff| println!("{}", diff));
+                    process::exit(1);
+                }
+                Err(error) => fail(format!("failed to replay the transcript: {}", error)),
+            }
+        }
+    }
+}
+
+/// Plays the game, and on a protocol error logs it and flushes the log before handing it back, so
+/// the log of the turn that broke is on disk before `fail` exits.
+fn run<B: Bot, R: BufRead, W: Write>(bot: &mut B, khala: Result<Khala<R, W>, ProtocolError>) -> Result<(), String> {
+    let mut khala = khala.map_err(|error| format!("failed to read the initial game state: {}", error))?;
+    if let Err(error) = play(bot, &mut khala) {
+        let message = format!("failed to read turn {}: {}", khala.turn_number + 1, error);
+        khala.logger_mut().error(message.as_str());
+        khala.logger_mut().flush();
+        return Err(message);
+    }
+    Ok(())
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+enum Session {
+    Live,
+    Capture(PathBuf),
+    Replay(PathBuf),
+}
+
+/// The logging and session options of the command line, and the arguments left.
+fn parse_args() -> (LogConfig, Session, Vec<String>) {
+    let (log_config, args) = LogConfig::from_env().with_args(env::args().skip(1)).unwrap_or_else(|error| fail(error));
+    let mut session = Session::Live;
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--transcript" => session = args.next().map_or(Session::Live, |path| Session::Capture(path.into())),
+            "--replay-transcript" => session = args.next().map_or(Session::Live, |path| Session::Replay(path.into())),
+            _ => rest.push(arg),
+        }
+    }
+    (log_config, session, rest)
+}
+
+/// The game loop of `run_bot` on any transport. Returns once the engine closes the input,
+/// which is how a game ends.
+pub fn play<B: Bot, R: BufRead, W: Write>(bot: &mut B, khala: &mut Khala<R, W>) -> Result<(), ProtocolError> {
+    bot.init(khala);
+    khala.ready(bot.name());
+    loop {
+        match khala.update() {
+            Ok(()) => {}
+            Err(ref error) if error.is_eof() => {
+                bot.finish(khala);
+                return Ok(());
+            }
+            Err(error) => return Err(error),
+        }
+        let started = Instant::now();
+        let commands = bot.play_turn(khala);
+        let elapsed = started.elapsed();
+        let turn_number = khala.turn_number;
+        khala.logger_mut().debug(format!("turn {} took {} ms for {} commands",
+                                         turn_number, elapsed.as_millis(), commands.commands().len()));
+        khala.end_turn(&commands);
+    }
+}
+
+/// The seed given as the first command line argument that is not an option of `run_bot`, the
+/// current time when there is none. Exits when it is not a number.
+pub fn seed_from_args() -> u64 {
+    let (_, _, args) = parse_args();
+    match args.first() {
+        Some(seed) => seed.parse().unwrap_or_else(|_| fail(format!("the seed {} is not a number", seed))),
+        None => SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+    }
+}
+
+/// Spreads `seed` over the 16 bytes `XorShiftRng` wants, the same way the bots always have.
+pub fn seeded_rng(seed: u64) -> XorShiftRng {
+    let mut seed_bytes = [0; 16];
+    for (x, byte) in seed_bytes.iter_mut().enumerate() {
+        *byte = ((seed >> (x % 8)) & 0xFF) as u8;
+    }
+    XorShiftRng::from_seed(seed_bytes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{play, seeded_rng, Bot};
+    use crate::halite::command::TurnCommands;
+    use crate::halite::game::{khala_from, Khala, TWO_TURNS};
+    use crate::halite::log::{Level, MemorySink};
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    
+    struct Counter {
+        turns: Vec<u32>,
+    }
+    
+    impl Bot for Counter {
+        fn name(&self) -> String {
+            "counter".to_string()
+        }
+        
+        fn play_turn<R, W>(&mut self, khala: &Khala<R, W>) -> TurnCommands {
+            self.turns.push(khala.turn_number);
+            let mut commands = TurnCommands::new(khala);
+            commands.spawn().unwrap();
+            commands
+        }
+    }
+    
+    #[test]
+    fn plays_until_the_engine_closes_the_input() {
+        let mut khala = khala_from(TWO_TURNS);
+        let log = MemorySink::new();
+        khala.logger_mut().add_sink(log.clone());
+        khala.logger_mut().set_level(Level::Debug);
+        let mut bot = Counter { turns: Vec::new() };
+        play(&mut bot, &mut khala).unwrap();
+        assert_eq!(bot.turns, vec![1, 2]);
+        assert_eq!(String::from_utf8(khala.writer().clone()).unwrap(), "counter\ng \ng \n");
+        let timings: Vec<_> = log.records().into_iter().filter(|record| record.message.contains(" took ")).collect();
+        assert_eq!(timings.iter().map(|record| record.turn).collect::<Vec<_>>(), vec![Some(1), Some(2)]);
+        
+        let mut broken = khala_from("{}\n1 0\n0 0 0\n1 1\n0\nx\n");
+        assert!(play(&mut bot, &mut broken).is_err());
+    }
+    
+    #[test]
+    fn derives_the_rng_from_the_seed_as_before() {
+        let seed = 0x1234_5678_9abc_def0u64;
+        let seed_bytes: Vec<u8> = (0..16).map(|x| ((seed >> (x % 8)) & 0xFF) as u8).collect();
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&seed_bytes);
+        assert_eq!(seeded_rng(seed).gen::<u64>(), XorShiftRng::from_seed(bytes).gen::<u64>());
+    }
+}
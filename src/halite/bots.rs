@@ -0,0 +1,212 @@
+//! Baseline bots to measure against, each with a binary in `src/bin`.
+
+use std::cmp::Reverse;
+use std::path::PathBuf;
+use rand::{Rng, XorShiftRng};
+use super::bot::{seeded_rng, Bot};
+use super::command::TurnCommands;
+use super::direction::Direction;
+use super::flog::Flog;
+use super::game::{Khala, PlayerId};
+use super::movement::MoveResolver;
+use super::navigation::Navigator;
+use super::position::{Position, RecursiveCellPosition};
+use super::registry::{Mission, ShipRegistry};
+
+/// Never does anything, so it ends with the halite it started with.
+#[derive(Clone, Debug, Default)]
+pub struct IdleBot;
+
+impl Bot for IdleBot {
+    fn name(&self) -> String {
+        "idle_bot".to_string()
+    }
+    
+    fn play_turn<R, W>(&mut self, khala: &Khala<R, W>) -> TurnCommands {
+        TurnCommands::new(khala)
+    }
+}
+
+/// Wanders around at random, sitting still on rich cells, and spawns during the first 200 turns.
+/// It never brings anything home.
+#[derive(Clone, Debug)]
+pub struct RandomBot {
+    rng: XorShiftRng,
+    my_id: PlayerId,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> RandomBot {
+        RandomBot {
+            rng: seeded_rng(seed),
+            my_id: 0,
+        }
+    }
+}
+
+impl Bot for RandomBot {
+    fn name(&self) -> String {
+        format!("bot_{}", self.my_id)
+    }
+    
+    fn init<R, W>(&mut self, khala: &Khala<R, W>) {
+        self.my_id = khala.my_id;
+    }
+    
+    fn play_turn<R, W>(&mut self, khala: &Khala<R, W>) -> TurnCommands {
+        let mut resolver = MoveResolver::new(khala);
+        
+        for ship in khala.ships_of(khala.my_id) {
+            let rich = khala.resource_map[ship.position] >= khala.game_constants.max_cell_production;
+            let directions = if !rich {
+                let mut directions = Direction::ALL_CARDINALS.to_vec();
+                self.rng.shuffle(&mut directions);
+                directions
+            } else {
+                vec![Direction::Still]
+            };
+            resolver.prefer(ship.ship_id, &directions).expect("every ship is commanded once");
+        }
+        
+        if khala.turn_number <= 200 &&
+            khala.player_owned_halite[khala.my_id as usize] >= khala.game_constants.new_entity_energy_cost {
+            resolver.spawn();
+        }
+        
+        let mut commands = TurnCommands::new(khala);
+        resolver.resolve().apply_to(&mut commands).expect("the resolver only moves our own ships");
+        commands
+    }
+}
+
+/// Mines the cell it is on until it runs low, then moves to the richest neighbor; goes home the
+/// cheapest way once nearly full or when the game is about to end. Spawns during the first half,
+/// as long as the fleet is not too big for the map.
+#[derive(Clone, Debug, Default)]
+pub struct GreedyBot {
+    ships: ShipRegistry,
+    /// notes on every ship's mission, saved to the path when the game is over
+    flog: Option<(Flog, PathBuf)>,
+}
+
+impl GreedyBot {
+    /// Cells with less halite than this are not worth staying on.
+    const MINING_THRESHOLD: u32 = 100;
+    /// Turns to spare when heading home for the end of the game.
+    const RECALL_MARGIN: u32 = 5;
+    /// Cells of the map per ship and player; with more ships they only block each other.
+    const CELLS_PER_SHIP: u32 = 16;
+    
+    pub fn new() -> GreedyBot {
+        GreedyBot::default()
+    }
+    
+    /// Writes a Fluorine flog into `path` after the game.
+    pub fn with_flog<P: Into<PathBuf>>(mut self, path: P) -> GreedyBot {
+        self.flog = Some((Flog::new(), path.into()));
+        self
+    }
+}
+
+impl Bot for GreedyBot {
+    fn name(&self) -> String {
+        "greedy_bot".to_string()
+    }
+    
+    fn play_turn<R, W>(&mut self, khala: &Khala<R, W>) -> TurnCommands {
+        let constants = &khala.game_constants;
+        self.ships.sync(khala, &mut ());
+        let homes: Vec<Position> =
+            khala.structures_of(khala.my_id).map(|structure| structure.position).collect();
+        let home = Navigator::for_khala(khala).dijkstra(&homes);
+        let turns_left = constants.max_turns.saturating_sub(khala.turn_number);
+        
+        for record in self.ships.iter_mut() {
+            let ship = record.ship;
+            let turns_home = home.cost(ship.position).map_or(0, |cost| cost.turns);
+            if turns_left <= turns_home + GreedyBot::RECALL_MARGIN {
+                record.set_mission(Mission::EndgameRecall, None);
+            } else if ship.cargo >= constants.max_energy * 9 / 10 {
+                record.set_mission(Mission::Returning, None);
+            } else if record.mission == Mission::Returning && ship.cargo == 0 {
+                record.set_mission(Mission::Exploring, None);
+            }
+            if let Some((flog, _)) = &mut self.flog {
+                let color = match record.mission {
+                    Mission::Returning | Mission::EndgameRecall => "#3399ff",
+                    _ => "#ffcc00",
+                };
+                let note = format!("ship {} {:?} with {} halite, {} turns from home",
+                                   ship.ship_id, record.mission, ship.cargo, turns_home);
+                flog.tag_colored(khala, ship.position, note, color);
+            }
+        }
+        
+        let mut resolver = MoveResolver::new(khala);
+        // ships on their way home get the cells they want first
+        let (going_home, mining): (Vec<_>, Vec<_>) = self.ships.iter().partition(|record| {
+            record.mission == Mission::Returning || record.mission == Mission::EndgameRecall
+        });
+        for record in going_home {
+            let direction = home.direction_from(record.ship.position).unwrap_or(Direction::Still);
+            resolver.prefer(record.ship_id(), &[direction]).expect("every ship is commanded once");
+        }
+        for record in mining {
+            let position = record.ship.position;
+            let mut directions = Vec::new();
+            if khala.resource_map[position] < GreedyBot::MINING_THRESHOLD {
+                directions = Direction::ALL_CARDINALS.to_vec();
+                directions.sort_by_key(|&direction| {
+                    Reverse(khala.resource_map[position.offset(direction, &khala.resource_map)])
+                });
+            }
+            resolver.prefer(record.ship_id(), &directions).expect("every ship is commanded once");
+        }
+        
+        let (width, height) = khala.resource_map.size();
+        let fleet_limit = width as usize * height as usize / (GreedyBot::CELLS_PER_SHIP * khala.num_players) as usize;
+        if khala.turn_number <= constants.max_turns / 2 && self.ships.len() < fleet_limit &&
+            khala.player_owned_halite[khala.my_id as usize] >= constants.new_entity_energy_cost {
+            resolver.spawn();
+        }
+        
+        let mut commands = TurnCommands::new(khala);
+        resolver.resolve().apply_to(&mut commands).expect("the resolver only moves our own ships");
+        commands
+    }
+    
+    fn finish<R, W>(&mut self, _khala: &Khala<R, W>) {
+        if let Some((flog, path)) = &self.flog {
+            if let Err(error) = flog.save(path) {
+                eprintln!("failed to save the flog into {}: {}", path.display(), error);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{GreedyBot, IdleBot, RandomBot};
+    use crate::halite::engine::GameConfig;
+    use crate::halite::engine::local::{play_local_game, LocalPlayer};
+    use crate::halite::log::LogConfig;
+    
+    #[test]
+    fn greedy_collects_more_than_it_spends() {
+        let mut config = GameConfig::new((16, 16), 4, 7);
+        config.constants.max_turns = 120;
+        let mut bots = vec![
+            LocalPlayer::new(GreedyBot::new()),
+            LocalPlayer::new(IdleBot),
+            LocalPlayer::new(RandomBot::new(7)),
+            LocalPlayer::new(IdleBot),
+        ];
+        let result = play_local_game(&config, &mut bots, &LogConfig::disabled()).unwrap();
+        assert_eq!(result.turns, 120);
+        assert_eq!(result.bot_names, vec!["greedy_bot", "idle_bot", "bot_2", "idle_bot"]);
+        assert!(result.players[0].halite > result.players[1].halite);
+        assert_eq!(result.players[1].halite, 5000);
+        assert_eq!(result.players[0].rank, 1);
+    }
+}
@@ -0,0 +1,246 @@
+//! What a bot can tell the engine at the end of a turn, checked against the game state before
+//! anything is written.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use super::direction::Direction;
+use super::error::InvalidCommand;
+use super::game::{Grid2D, HaliteAmount, Khala, PlayerId, ShipId};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Command {
+    Spawn,
+    Move(ShipId, Direction),
+    /// turn the ship into a dropoff
+    Construct(ShipId),
+}
+
+impl Command {
+    /// The ship the command is for, `None` for a spawn.
+    pub fn ship_id(&self) -> Option<ShipId> {
+        match *self {
+            Command::Spawn => None,
+            Command::Move(ship_id, _) | Command::Construct(ship_id) => Some(ship_id),
+        }
+    }
+    
+    /// Parses a command line, or several command lines joined with spaces, the way the engine
+    /// reads them.
+    pub fn parse_line(line: &str) -> Result<Vec<Command>, String> {
+        let mut tokens = line.split_whitespace();
+        let mut commands = Vec::new();
+        let ship_id = |token: Option<&str>, command: &str| -> Result<ShipId, String> {
+            token.and_then(|token| token.parse().ok())
+                 .ok_or_else(|| format!("command {} expects a ship id", command))
+        };
+        while let Some(token) = tokens.next() {
+            let command = match token {
+                "g" => Command::Spawn,
+                "c" => Command::Construct(ship_id(tokens.next(), "c")?),
+                "m" => {
+                    let ship_id = ship_id(tokens.next(), "m")?;
+                    let direction = tokens.next();
+                    match direction.and_then(|direction| direction.parse::<Direction>().ok()) {
+                        Some(direction) => Command::Move(ship_id, direction),
+                        None => return Err(format!("invalid direction {:?} for ship {}", direction, ship_id)),
+                    }
+                }
+                other => return Err(format!("unknown command {:?}", other)),
+            };
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Spawn => write!(f, "g"),
+            Command::Move(ship_id, direction) => write!(f, "m {} {}", ship_id, direction),
+            Command::Construct(ship_id) => write!(f, "c {}", ship_id),
+        }
+    }
+}
+
+/// The commands of one turn. Every command is checked when it is added, so whatever ends up in
+/// here is something the engine accepts: one spawn at most, one command per ship, only own
+/// ships, no dropoff on top of a structure and never more halite spent than the bot has.
+#[derive(Clone, Debug)]
+pub struct TurnCommands {
+    my_id: PlayerId,
+    commands: Vec<Command>,
+    commanded: HashSet<ShipId>,
+    owners: HashMap<ShipId, PlayerId>,
+    /// what turning each of my ships into a dropoff costs, `None` when it stands on a structure
+    construct_costs: HashMap<ShipId, Option<i64>>,
+    spawn_cost: i64,
+    /// halite left after the commands so far; constructing can add to it
+    remaining: i64,
+}
+
+impl TurnCommands {
+    /// An empty set of commands for the turn `khala` was last updated to.
+    pub fn new<R, W>(khala: &Khala<R, W>) -> TurnCommands {
+        let owners = khala.ships.iter().map(|ship| (ship.ship_id, ship.owner_id)).collect();
+        let my_ships = khala.ships.iter().filter(|ship| ship.owner_id == khala.my_id);
+        let construct_costs = my_ships.map(|ship| {
+            let occupied = khala.structures.iter().any(|structure| structure.position == ship.position);
+            let cost = khala.game_constants.dropoff_cost as i64 -
+                ship.cargo as i64 - khala.get_at_position(ship.position) as i64;
+            (ship.ship_id, if occupied { None } else { Some(cost) })
+        }).collect();
+        TurnCommands {
+            my_id: khala.my_id,
+            commands: Vec::new(),
+            commanded: HashSet::new(),
+            owners,
+            construct_costs,
+            spawn_cost: khala.game_constants.new_entity_energy_cost as i64,
+            remaining: khala.player_owned_halite.get(khala.my_id as usize).cloned().unwrap_or(0) as i64,
+        }
+    }
+    
+    /// Adds `command`, or leaves everything as it was if the engine would reject it.
+    pub fn push(&mut self, command: Command) -> Result<(), InvalidCommand> {
+        let cost = match command {
+            Command::Spawn => {
+                if self.has_spawn() {
+                    return Err(InvalidCommand::SecondSpawn);
+                }
+                self.spawn_cost
+            }
+            Command::Move(ship_id, _) | Command::Construct(ship_id) => {
+                match self.owners.get(&ship_id) {
+                    Some(&owner_id) if owner_id == self.my_id => {}
+                    Some(&owner_id) => return Err(InvalidCommand::ForeignShip { ship_id, owner_id }),
+                    None => return Err(InvalidCommand::UnknownShip(ship_id)),
+                }
+                if self.commanded.contains(&ship_id) {
+                    return Err(InvalidCommand::AlreadyCommanded(ship_id));
+                }
+                match command {
+                    Command::Construct(_) => match self.construct_costs[&ship_id] {
+                        Some(cost) => cost,
+                        None => return Err(InvalidCommand::StructureInTheWay(ship_id)),
+                    },
+                    _ => 0,
+                }
+            }
+        };
+        if cost > self.remaining {
+            return Err(InvalidCommand::NotEnoughHalite {
+                needed: cost as HaliteAmount,
+                available: self.remaining.max(0) as HaliteAmount,
+            });
+        }
+        self.remaining -= cost;
+        if let Some(ship_id) = command.ship_id() {
+            self.commanded.insert(ship_id);
+        }
+        self.commands.push(command);
+        Ok(())
+    }
+    
+    pub fn spawn(&mut self) -> Result<(), InvalidCommand> {
+        self.push(Command::Spawn)
+    }
+    
+    pub fn move_ship(&mut self, ship_id: ShipId, direction: Direction) -> Result<(), InvalidCommand> {
+        self.push(Command::Move(ship_id, direction))
+    }
+    
+    pub fn construct(&mut self, ship_id: ShipId) -> Result<(), InvalidCommand> {
+        self.push(Command::Construct(ship_id))
+    }
+    
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+    
+    pub fn is_commanded(&self, ship_id: ShipId) -> bool {
+        self.commanded.contains(&ship_id)
+    }
+    
+    pub fn has_spawn(&self) -> bool {
+        self.commands.contains(&Command::Spawn)
+    }
+    
+    /// Halite the bot still has to spend this turn.
+    pub fn remaining_halite(&self) -> HaliteAmount {
+        self.remaining.max(0) as HaliteAmount
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, TurnCommands};
+    use crate::halite::direction::Direction;
+    use crate::halite::error::InvalidCommand;
+    use crate::halite::game::khala_from;
+    use crate::halite::update::Update;
+    
+    // player 1 owns ship 2 on a 700 halite cell with 900 cargo and ship 3 on its shipyard
+    const GAME: &str = "\
+{\"NEW_ENTITY_ENERGY_COST\":1000,\"DROPOFF_COST\":4000}
+2 1
+0 0 0
+1 1 0
+2 2
+10 20
+30 700
+1
+0 1 0 0
+0 0 0 0
+1 2 0 3000
+2 1 1 900
+3 1 0 0
+0
+";
+    
+    fn commands() -> TurnCommands {
+        let mut khala = khala_from(GAME);
+        khala.update().unwrap();
+        TurnCommands::new(&khala)
+    }
+    
+    #[test]
+    fn rejects_what_the_engine_would_reject() {
+        let mut commands = commands();
+        assert_eq!(commands.move_ship(0, Direction::North), Err(InvalidCommand::ForeignShip {
+            ship_id: 0,
+            owner_id: 0,
+        }));
+        assert_eq!(commands.move_ship(9, Direction::North), Err(InvalidCommand::UnknownShip(9)));
+        assert_eq!(commands.construct(3), Err(InvalidCommand::StructureInTheWay(3)));
+        assert_eq!(commands.move_ship(2, Direction::East), Ok(()));
+        assert_eq!(commands.construct(2), Err(InvalidCommand::AlreadyCommanded(2)));
+        assert_eq!(commands.spawn(), Ok(()));
+        assert_eq!(commands.spawn(), Err(InvalidCommand::SecondSpawn));
+        assert_eq!(commands.remaining_halite(), 2000);
+        assert_eq!(commands.commands(), &[Command::Move(2, Direction::East), Command::Spawn]);
+    }
+    
+    #[test]
+    fn counts_cargo_and_cell_towards_a_dropoff() {
+        let mut commands = commands();
+        commands.spawn().unwrap();
+        commands.spawn().unwrap_err();
+        // 4000 - 900 cargo - 700 halite under the ship leaves 2400 to pay, only 2000 are left
+        assert_eq!(commands.construct(2), Err(InvalidCommand::NotEnoughHalite {
+            needed: 2400,
+            available: 2000,
+        }));
+        assert!(!commands.is_commanded(2));
+    }
+    
+    #[test]
+    fn parses_what_it_prints() {
+        let commands = vec![Command::Spawn, Command::Move(3, Direction::Still), Command::Construct(4)];
+        let line: Vec<String> = commands.iter().map(|command| command.to_string()).collect();
+        assert_eq!(line.join(" "), "g m 3 o c 4");
+        assert_eq!(Command::parse_line(&line.join(" ")), Ok(commands));
+        assert!(Command::parse_line("m 3 x").is_err());
+    }
+}
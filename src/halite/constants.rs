@@ -0,0 +1,160 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use super::error::ProtocolError;
+use super::input::Line;
+
+/// Declares `Constants` together with the engine key and the engine default of every field.
+///
+/// A key missing from the engine's line falls back to its default, a key present with a value
+/// of the wrong type is reported as `ProtocolError::UnknownConstant`.
+macro_rules! game_constants {
+    ($($field: ident: $ty: ty = $key: expr, $default: expr;)*) => {
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Constants {
+            $(pub $field: $ty,)*
+            /// every key sent by the engine that has no typed field above
+            pub extra: BTreeMap<String, Value>,
+        }
+        
+        impl Default for Constants {
+            fn default() -> Constants {
+                Constants {
+                    $($field: $default,)*
+                    extra: BTreeMap::new(),
+                }
+            }
+        }
+        
+        impl Constants {
+            /// Builds the constants out of an already parsed JSON object.
+            /// On failure the name of the offending key is returned.
+            pub fn from_json_map(mut map: Map<String, Value>) -> Result<Constants, String> {
+                let defaults = Constants::default();
+                $(
+                    let $field: $ty = match map.remove($key) {
+                        Some(value) => serde_json::from_value(value).map_err(|_| $key.to_string())?,
+                        None => defaults.$field,
+                    };
+                )*
+                Ok(Constants {
+                    $($field,)*
+                    extra: map.into_iter().collect(),
+                })
+            }
+            
+            /// The constants as the JSON object the engine sends on the first line.
+            pub fn to_json_map(&self) -> Map<String, Value> {
+                let mut map: Map<String, Value> = self.extra.clone().into_iter().collect();
+                $(map.insert($key.to_string(), Value::from(self.$field));)*
+                map
+            }
+        }
+    };
+}
+
+game_constants! {
+    capture_enabled: bool = "CAPTURE_ENABLED", false;
+    capture_radius: u32 = "CAPTURE_RADIUS", 3;
+    default_map_height: u32 = "DEFAULT_MAP_HEIGHT", 32;
+    default_map_width: u32 = "DEFAULT_MAP_WIDTH", 32;
+    dropoff_cost: u32 = "DROPOFF_COST", 4000;
+    dropoff_penalty_ratio: u32 = "DROPOFF_PENALTY_RATIO", 4;
+    extract_ratio: u32 = "EXTRACT_RATIO", 4;
+    factor_exp_1: f64 = "FACTOR_EXP_1", 2.0;
+    factor_exp_2: f64 = "FACTOR_EXP_2", 2.0;
+    initial_energy: u32 = "INITIAL_ENERGY", 5000;
+    inspiration_enabled: bool = "INSPIRATION_ENABLED", true;
+    inspiration_radius: u32 = "INSPIRATION_RADIUS", 4;
+    inspiration_ship_count: u32 = "INSPIRATION_SHIP_COUNT", 2;
+    inspired_bonus_multiplier: f64 = "INSPIRED_BONUS_MULTIPLIER", 2.0;
+    inspired_extract_ratio: u32 = "INSPIRED_EXTRACT_RATIO", 4;
+    inspired_move_cost_ratio: u32 = "INSPIRED_MOVE_COST_RATIO", 10;
+    max_cell_production: u32 = "MAX_CELL_PRODUCTION", 1000;
+    max_energy: u32 = "MAX_ENERGY", 1000;
+    max_players: u32 = "MAX_PLAYERS", 16;
+    max_turns: u32 = "MAX_TURNS", 400;
+    max_turn_threshold: u32 = "MAX_TURN_THRESHOLD", 64;
+    min_cell_production: u32 = "MIN_CELL_PRODUCTION", 900;
+    min_turns: u32 = "MIN_TURNS", 400;
+    min_turn_threshold: u32 = "MIN_TURN_THRESHOLD", 32;
+    move_cost_ratio: u32 = "MOVE_COST_RATIO", 10;
+    new_entity_energy_cost: u32 = "NEW_ENTITY_ENERGY_COST", 1000;
+    persistence: f64 = "PERSISTENCE", 0.7;
+    ships_above_for_capture: u32 = "SHIPS_ABOVE_FOR_CAPTURE", 3;
+    strict_errors: bool = "STRICT_ERRORS", false;
+    game_seed: u64 = "game_seed", 0;
+}
+
+impl Constants {
+    /// Parses the first line the engine sends, a single JSON object.
+    pub fn from_line(line: &Line) -> Result<Constants, ProtocolError> {
+        let map: Map<String, Value> = serde_json::from_str(&line.raw).map_err(|error| {
+            ProtocolError::BadJson {
+                line: line.number,
+                raw: line.raw.clone(),
+                reason: error.to_string(),
+            }
+        })?;
+        Constants::from_json_map(map).map_err(|name| ProtocolError::UnknownConstant {
+            line: line.number,
+            raw: line.raw.clone(),
+            name,
+        })
+    }
+    
+    /// Looks up a constant this struct has no field for.
+    pub fn get_extra(&self, key: &str) -> Option<&Value> {
+        self.extra.get(key)
+    }
+    
+    pub fn to_json_string(&self) -> String {
+        Value::Object(self.to_json_map()).to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Constants;
+    use crate::halite::error::ProtocolError;
+    use crate::halite::input::Line;
+    
+    fn line(raw: &str) -> Line {
+        Line::read(&mut format!("{}\n", raw).as_bytes(), &mut 0).unwrap()
+    }
+    
+    #[test]
+    fn parses_typed_and_unknown_keys() {
+        let constants = Constants::from_line(&line(
+            r#"{"MAX_ENERGY":1000,"EXTRACT_RATIO":4,"PERSISTENCE":0.7,"STRICT_ERRORS":false,"NEW_KEY":{"nested":"a,b:{c}"}}"#
+        )).unwrap();
+        assert_eq!(constants.max_energy, 1000);
+        assert_eq!(constants.extract_ratio, 4);
+        assert_eq!(constants.persistence, 0.7);
+        assert!(!constants.strict_errors);
+        assert_eq!(constants.get_extra("NEW_KEY").unwrap()["nested"], "a,b:{c}");
+        assert!(constants.get_extra("MAX_ENERGY").is_none());
+    }
+    
+    #[test]
+    fn round_trips_through_json() {
+        let mut constants = Constants {
+            game_seed: 1541022553,
+            ..Constants::default()
+        };
+        constants.extra.insert("CUSTOM".to_string(), 12.into());
+        assert_eq!(Constants::from_line(&line(&constants.to_json_string())).unwrap(), constants);
+    }
+    
+    #[test]
+    fn reports_bad_values_and_bad_json() {
+        match Constants::from_line(&line(r#"{"MAX_TURNS":"many"}"#)) {
+            Err(ProtocolError::UnknownConstant { name, .. }) => assert_eq!(name, "MAX_TURNS"),
+            other => panic!("unexpected {:?}", other),
+        }
+        match Constants::from_line(&line(r#"{"MAX_TURNS":"#)) {
+            Err(ProtocolError::BadJson { line: 1, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Where a ship goes this turn; `Still` keeps it in place.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Debug)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+    Still,
+}
+
+impl Direction {
+    /// Clockwise, starting from north.
+    pub const ALL_CARDINALS: [Direction; 4] =
+        [Direction::North, Direction::East, Direction::South, Direction::West];
+    
+    /// The four cardinals followed by `Still`.
+    pub const ALL: [Direction; 5] =
+        [Direction::North, Direction::East, Direction::South, Direction::West, Direction::Still];
+    
+    /// The character the engine expects in a move command.
+    pub fn to_char(self) -> char {
+        match self {
+            Direction::North => 'n',
+            Direction::East => 'e',
+            Direction::South => 's',
+            Direction::West => 'w',
+            Direction::Still => 'o',
+        }
+    }
+    
+    pub fn from_char(c: char) -> Option<Direction> {
+        match c {
+            'n' => Some(Direction::North),
+            'e' => Some(Direction::East),
+            's' => Some(Direction::South),
+            'w' => Some(Direction::West),
+            'o' => Some(Direction::Still),
+            _ => None,
+        }
+    }
+    
+    /// `(dx, dy)` of one step; north is towards `y == 0`.
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+            Direction::Still => (0, 0),
+        }
+    }
+    
+    pub fn invert(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::Still => Direction::Still,
+        }
+    }
+    
+    pub fn rotate_cw(self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+            Direction::Still => Direction::Still,
+        }
+    }
+    
+    pub fn rotate_ccw(self) -> Direction {
+        self.rotate_cw().invert()
+    }
+}
+
+/// Parses the one character form used in move commands.
+impl FromStr for Direction {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Direction, String> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(Direction::from_char), chars.next()) {
+            (Some(direction), None) => Ok(direction),
+            _ => Err(format!("invalid direction {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Direction;
+    
+    #[test]
+    fn rotations_and_inversions_are_consistent() {
+        for &direction in Direction::ALL.iter() {
+            assert_eq!(Direction::from_char(direction.to_char()), Some(direction));
+            assert_eq!(direction.invert().invert(), direction);
+            assert_eq!(direction.rotate_cw().rotate_ccw(), direction);
+            assert_eq!(direction.rotate_cw().rotate_cw(), direction.invert());
+        }
+        assert_eq!(Direction::from_char('x'), None);
+        assert_eq!("e".parse::<Direction>(), Ok(Direction::East));
+        assert!("ee".parse::<Direction>().is_err());
+        assert_eq!(Direction::West.rotate_ccw(), Direction::South);
+        assert_eq!(Direction::Still.to_string(), "o");
+    }
+}
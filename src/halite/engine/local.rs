@@ -0,0 +1,222 @@
+//! Plays whole games inside one process: every bot gets its own `Khala` connected to the
+//! `Engine` through in-memory pipes, so it goes through exactly the same protocol code as in
+//! a real game.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read, Write};
+use std::rc::Rc;
+use super::{Engine, GameConfig, GameResult};
+use super::super::bot::Bot;
+use super::super::error::ProtocolError;
+use super::super::command::TurnCommands;
+use super::super::game::Khala;
+use super::super::log::LogConfig;
+use super::super::update::Update;
+
+/// The reading end of an in-memory pipe.
+#[derive(Debug)]
+pub struct PipeReader {
+    shared: Rc<RefCell<VecDeque<u8>>>,
+    buffer: Vec<u8>,
+    consumed: usize,
+}
+
+/// The writing end of an in-memory pipe.
+#[derive(Clone, Debug)]
+pub struct PipeWriter {
+    shared: Rc<RefCell<VecDeque<u8>>>,
+}
+
+pub fn pipe() -> (PipeReader, PipeWriter) {
+    let shared = Rc::new(RefCell::new(VecDeque::new()));
+    (PipeReader { shared: shared.clone(), buffer: Vec::new(), consumed: 0 }, PipeWriter { shared })
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+        Ok(count)
+    }
+}
+
+impl BufRead for PipeReader {
+    /// Returns an empty slice, i.e. end of file, once everything written so far has been read.
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.consumed >= self.buffer.len() {
+            self.buffer = self.shared.borrow_mut().drain(..).collect();
+            self.consumed = 0;
+        }
+        Ok(&self.buffer[self.consumed..])
+    }
+    
+    fn consume(&mut self, amount: usize) {
+        self.consumed = (self.consumed + amount).min(self.buffer.len());
+    }
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.shared.borrow_mut().extend(buf.iter());
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub type LocalKhala = Khala<PipeReader, PipeWriter>;
+
+/// Any `Bot` taking a seat in a local game, so bots of different types can play each other.
+pub struct LocalPlayer<'a>(Box<dyn Seated + 'a>);
+
+impl<'a> LocalPlayer<'a> {
+    pub fn new<B: Bot + 'a>(bot: B) -> LocalPlayer<'a> {
+        LocalPlayer(Box::new(bot))
+    }
+}
+
+/// `Bot` on the pipes of a local game, which unlike `Bot` can be boxed.
+trait Seated {
+    fn name(&self) -> String;
+    fn init(&mut self, khala: &LocalKhala);
+    fn play_turn(&mut self, khala: &LocalKhala) -> TurnCommands;
+    fn finish(&mut self, khala: &LocalKhala);
+}
+
+impl<B: Bot> Seated for B {
+    fn name(&self) -> String {
+        Bot::name(self)
+    }
+    
+    fn init(&mut self, khala: &LocalKhala) {
+        Bot::init(self, khala);
+    }
+    
+    fn play_turn(&mut self, khala: &LocalKhala) -> TurnCommands {
+        Bot::play_turn(self, khala)
+    }
+    
+    fn finish(&mut self, khala: &LocalKhala) {
+        Bot::finish(self, khala);
+    }
+}
+
+struct Seat {
+    khala: LocalKhala,
+    to_bot: PipeWriter,
+    from_bot: PipeReader,
+}
+
+impl Seat {
+    fn read_line(&mut self) -> String {
+        let mut line = String::new();
+        // reading from a pipe cannot fail, an empty line means the bot said nothing
+        let _ = self.from_bot.read_line(&mut line);
+        line.trim_end().to_string()
+    }
+}
+
+/// Plays a complete game between `bots`, the first bot being player 0. Each bot logs into
+/// `khala_bot_<id>.log` as `log_config` says.
+pub fn play_local_game(config: &GameConfig, bots: &mut [LocalPlayer],
+                       log_config: &LogConfig) -> Result<GameResult, ProtocolError> {
+    assert_eq!(bots.len() as u32, config.num_players, "one bot per player is needed");
+    let mut engine = Engine::new(config);
+    
+    let mut seats = Vec::new();
+    let mut bot_names = Vec::new();
+    for (player_id, LocalPlayer(bot)) in bots.iter_mut().enumerate() {
+        let (bot_input, mut to_bot) = pipe();
+        let (mut from_bot, bot_output) = pipe();
+        to_bot.write_all(engine.init_text(player_id as u32).as_bytes()).unwrap();
+        let mut khala = Khala::read_with_log(bot_input, bot_output, log_config)?;
+        bot.init(&khala);
+        khala.ready(bot.name());
+        let mut name = String::new();
+        let _ = from_bot.read_line(&mut name);
+        bot_names.push(name.trim_end().to_string());
+        seats.push(Seat { khala, to_bot, from_bot });
+    }
+    
+    let mut turns = 0;
+    while !engine.is_finished() {
+        let frame = engine.frame_text();
+        let mut commands = Vec::new();
+        for (player_id, seat) in seats.iter_mut().enumerate() {
+            if !engine.players[player_id].is_alive() {
+                commands.push(Vec::new());
+                continue;
+            }
+            seat.to_bot.write_all(frame.as_bytes()).unwrap();
+            seat.khala.update()?;
+            let turn_commands = bots[player_id].0.play_turn(&seat.khala);
+            seat.khala.end_turn(&turn_commands);
+            commands.push(vec![seat.read_line()]);
+        }
+        engine.process_turn(&commands);
+        turns += 1;
+    }
+    for (LocalPlayer(bot), seat) in bots.iter_mut().zip(seats.iter()) {
+        bot.finish(&seat.khala);
+    }
+    
+    Ok(GameResult {
+        bot_names,
+        turns,
+        players: engine.results(),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{play_local_game, LocalPlayer};
+    use crate::halite::bot::Bot;
+    use crate::halite::bots::IdleBot;
+    use crate::halite::engine::GameConfig;
+    use crate::halite::command::TurnCommands;
+    use crate::halite::direction::Direction;
+    use crate::halite::game::Khala;
+    use crate::halite::log::LogConfig;
+    
+    /// Spawns once and keeps its ship on the shipyard.
+    struct Spawner;
+    
+    impl Bot for Spawner {
+        fn name(&self) -> String {
+            "spawner".to_string()
+        }
+        
+        fn play_turn<R, W>(&mut self, khala: &Khala<R, W>) -> TurnCommands {
+            let mut commands = TurnCommands::new(khala);
+            for ship in khala.ships_of(khala.my_id) {
+                commands.move_ship(ship.ship_id, Direction::Still).unwrap();
+            }
+            if khala.turn_number == 1 {
+                commands.spawn().unwrap();
+            }
+            commands
+        }
+    }
+    
+    #[test]
+    fn plays_a_whole_game_in_process() {
+        let mut config = GameConfig::new((8, 8), 2, 3);
+        config.constants.max_turns = 12;
+        let mut bots = vec![LocalPlayer::new(Spawner), LocalPlayer::new(IdleBot)];
+        let result = play_local_game(&config, &mut bots, &LogConfig::disabled()).unwrap();
+        
+        assert_eq!(result.turns, 12);
+        assert_eq!(result.bot_names, vec!["spawner", "idle_bot"]);
+        // the spawned ship sits on the shipyard and never mines
+        assert_eq!(result.players[0].halite, 4000);
+        assert_eq!(result.players[0].rank, 2);
+        assert_eq!(result.players[1].halite, 5000);
+        assert_eq!(result.players[1].rank, 1);
+    }
+}
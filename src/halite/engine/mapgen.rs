@@ -0,0 +1,100 @@
+use rand::Rng;
+use super::super::constants::Constants;
+use super::super::game::{HaliteAmount, MapSize};
+use super::super::grid::Grid;
+use super::super::position::Position;
+
+/// A freshly generated map: halite per cell and one shipyard per player.
+pub struct GeneratedMap {
+    pub resource_map: Grid<HaliteAmount>,
+    pub shipyards: Vec<Position>,
+}
+
+/// Generates a symmetric map from a simplified value noise, not the official engine's generator:
+/// one noise tile per player, mirrored horizontally for the second player and vertically for the
+/// third and fourth, with the shipyard in the middle of every tile.
+pub fn generate<G: Rng>(map_size: MapSize,
+                        num_players: u32,
+                        constants: &Constants,
+                        rng: &mut G) -> GeneratedMap {
+    let (width, height) = (map_size.0 as usize, map_size.1 as usize);
+    let tile_width = if num_players > 1 { width / 2 } else { width };
+    let tile_height = if num_players > 2 { height / 2 } else { height };
+    
+    let noise = fractal_noise(tile_width, tile_height, constants.persistence, rng);
+    let max_production = rng.gen_range(constants.min_cell_production,
+                                       constants.max_cell_production + 1) as f64;
+    let tile: Vec<Vec<HaliteAmount>> = noise.iter().map(|row| {
+        row.iter()
+           .map(|value| (value.powf(constants.factor_exp_1) * max_production) as HaliteAmount)
+           .collect()
+    }).collect();
+    
+    let mirror = |coordinate: usize, tile_size: usize, size: usize| {
+        if coordinate < tile_size {
+            coordinate
+        } else {
+            (size - 1 - coordinate).min(tile_size - 1)
+        }
+    };
+    
+    let mut resource_map = Grid::from_fn(map_size, |position| {
+        tile[mirror(position.y as usize, tile_height, height)][mirror(position.x as usize, tile_width, width)]
+    });
+    
+    let (home_x, home_y) = (tile_width / 2, tile_height / 2);
+    let shipyards: Vec<Position> = (0..num_players as usize).map(|player| {
+        let x = if player % 2 == 1 { width - 1 - home_x } else { home_x };
+        let y = if player >= 2 { height - 1 - home_y } else { home_y };
+        Position::new(x as i32, y as i32)
+    }).collect();
+    for &shipyard in shipyards.iter() {
+        resource_map[shipyard] = 0;
+    }
+    
+    GeneratedMap {
+        resource_map,
+        shipyards,
+    }
+}
+
+/// Sums octaves of smoothly interpolated lattice noise, each octave at twice the frequency and
+/// `persistence` times the amplitude of the previous one. Values are normalized into `[0, 1]`.
+fn fractal_noise<G: Rng>(width: usize, height: usize, persistence: f64, rng: &mut G) -> Vec<Vec<f64>> {
+    let mut noise = vec![vec![0.0; width]; height];
+    let mut spacing = (width.max(height) / 2).max(1);
+    let mut amplitude = 1.0;
+    loop {
+        let lattice_width = width / spacing + 2;
+        let lattice_height = height / spacing + 2;
+        let lattice: Vec<Vec<f64>> = (0..lattice_height)
+            .map(|_| (0..lattice_width).map(|_| rng.gen::<f64>()).collect())
+            .collect();
+        let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+        for (y, row) in noise.iter_mut().enumerate() {
+            let (lattice_y, ty) = (y / spacing, smooth((y % spacing) as f64 / spacing as f64));
+            for (x, value) in row.iter_mut().enumerate() {
+                let (lattice_x, tx) = (x / spacing, smooth((x % spacing) as f64 / spacing as f64));
+                let top = lattice[lattice_y][lattice_x] * (1.0 - tx) +
+                    lattice[lattice_y][lattice_x + 1] * tx;
+                let bottom = lattice[lattice_y + 1][lattice_x] * (1.0 - tx) +
+                    lattice[lattice_y + 1][lattice_x + 1] * tx;
+                *value += amplitude * (top * (1.0 - ty) + bottom * ty);
+            }
+        }
+        if spacing == 1 {
+            break;
+        }
+        spacing /= 2;
+        amplitude *= persistence;
+    }
+    
+    let (min, max) = noise.iter().flatten().fold((f64::MAX, f64::MIN), |(min, max), &value| {
+        (min.min(value), max.max(value))
+    });
+    let range = if max > min { max - min } else { 1.0 };
+    for value in noise.iter_mut().flatten() {
+        *value = (*value - min) / range;
+    }
+    noise
+}
@@ -0,0 +1,651 @@
+//! A pure Rust implementation of the Halite III game rules, so bots can be played against each
+//! other without the official engine binary.
+
+pub mod local;
+pub mod mapgen;
+pub mod process;
+pub mod tournament;
+
+use rand::{SeedableRng, XorShiftRng};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use super::command::Command;
+use super::constants::Constants;
+use super::direction::Direction;
+use super::game::{CommandString, HaliteAmount, MapSize, PlayerId, Ship, ShipId, Structure,
+                  StructureId, StructureType};
+use super::grid::Grid;
+use super::position::{Position, RecursiveCellPosition};
+
+/// Everything needed to set up one game.
+#[derive(Clone, Debug)]
+pub struct GameConfig {
+    pub map_size: MapSize,
+    pub num_players: u32,
+    pub seed: u64,
+    pub constants: Constants,
+}
+
+impl GameConfig {
+    /// The turn limit of the biggest maps; `Constants` defaults to the one of the smallest.
+    const LONGEST_GAME: u32 = 500;
+    
+    /// A game with the official constants, and a turn limit growing linearly with the map size
+    /// from `min_turns` up to `LONGEST_GAME`.
+    pub fn new(map_size: MapSize, num_players: u32, seed: u64) -> GameConfig {
+        let mut constants = Constants {
+            default_map_width: map_size.0,
+            default_map_height: map_size.1,
+            game_seed: seed,
+            ..Constants::default()
+        };
+        let size = map_size.0.max(map_size.1)
+                             .max(constants.min_turn_threshold)
+                             .min(constants.max_turn_threshold);
+        constants.max_turns = constants.min_turns +
+            (size - constants.min_turn_threshold) * (GameConfig::LONGEST_GAME - constants.min_turns) /
+                (constants.max_turn_threshold - constants.min_turn_threshold).max(1);
+        GameConfig {
+            map_size,
+            num_players,
+            seed,
+            constants,
+        }
+    }
+    
+    pub fn validate(&self) -> Result<(), String> {
+        if ![1, 2, 4].contains(&self.num_players) {
+            return Err(format!("unsupported number of players: {}", self.num_players));
+        }
+        if self.map_size.0 < 4 || self.map_size.1 < 4 {
+            return Err(format!("map of {}x{} is too small", self.map_size.0, self.map_size.1));
+        }
+        if self.constants.extract_ratio == 0 || self.constants.inspired_extract_ratio == 0 ||
+            self.constants.move_cost_ratio == 0 || self.constants.inspired_move_cost_ratio == 0 {
+            return Err("extract and move cost ratios must not be zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PlayerState {
+    pub player_id: PlayerId,
+    pub energy: HaliteAmount,
+    pub shipyard: Position,
+    /// why the player was removed from the game, if it was
+    pub error: Option<String>,
+    /// the last turn the player was still in the game
+    pub last_turn_alive: u32,
+    /// halite brought back to own structures over the whole game
+    pub deposited: HaliteAmount,
+}
+
+impl PlayerState {
+    pub fn is_alive(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A command that could not be carried out; the offending player is removed from the game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandError {
+    pub player_id: PlayerId,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerResult {
+    pub player_id: PlayerId,
+    /// 1 is the winner
+    pub rank: u32,
+    pub halite: HaliteAmount,
+    pub last_turn_alive: u32,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameResult {
+    pub bot_names: Vec<String>,
+    /// number of turns actually played
+    pub turns: u32,
+    pub players: Vec<PlayerResult>,
+}
+
+/// Something that happened during the last turn that cannot be seen from the next frame alone.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    Spawn {
+        owner_id: PlayerId,
+        ship_id: ShipId,
+        position: Position,
+    },
+    Construct {
+        owner_id: PlayerId,
+        structure_id: StructureId,
+        position: Position,
+    },
+    /// ships destroyed by colliding with each other
+    Shipwreck {
+        position: Position,
+        ships: Vec<ShipId>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Engine {
+    pub constants: Constants,
+    pub map_size: MapSize,
+    pub resource_map: Grid<HaliteAmount>,
+    pub players: Vec<PlayerState>,
+    pub ships: BTreeMap<ShipId, Ship>,
+    pub structures: Vec<Structure>,
+    /// the turn the next frame describes, starting at 1
+    pub turn_number: u32,
+    inspired: HashSet<ShipId>,
+    changed_cells: BTreeSet<Position>,
+    events: Vec<GameEvent>,
+    next_ship_id: ShipId,
+    next_dropoff_id: StructureId,
+    finished: bool,
+}
+
+impl Engine {
+    pub fn new(config: &GameConfig) -> Engine {
+        let mut rng = XorShiftRng::seed_from_u64(config.seed);
+        let map = mapgen::generate(config.map_size, config.num_players, &config.constants, &mut rng);
+        let players: Vec<PlayerState> = map.shipyards.iter().enumerate().map(|(id, &shipyard)| {
+            PlayerState {
+                player_id: id as PlayerId,
+                energy: config.constants.initial_energy,
+                shipyard,
+                error: None,
+                last_turn_alive: 0,
+                deposited: 0,
+            }
+        }).collect();
+        let structures = players.iter().map(|player| Structure {
+            owner_id: player.player_id,
+            structure_id: 0x4000 + player.player_id,
+            position: player.shipyard,
+            structure_type: StructureType::Shipyard,
+        }).collect();
+        Engine {
+            constants: config.constants.clone(),
+            map_size: config.map_size,
+            resource_map: map.resource_map,
+            players,
+            ships: BTreeMap::new(),
+            structures,
+            turn_number: 1,
+            inspired: HashSet::new(),
+            changed_cells: BTreeSet::new(),
+            events: Vec::new(),
+            next_ship_id: 0,
+            next_dropoff_id: 0,
+            finished: false,
+        }
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.finished || self.turn_number > self.constants.max_turns
+    }
+    
+    pub fn total_halite(&self) -> HaliteAmount {
+        self.resource_map.values().sum()
+    }
+    
+    /// The initial block of lines the engine sends to `player_id`, which `Khala::read_from`
+    /// parses.
+    pub fn init_text(&self, player_id: PlayerId) -> String {
+        let mut text = String::new();
+        writeln!(text, "{}", self.constants.to_json_string()).unwrap();
+        writeln!(text, "{} {}", self.players.len(), player_id).unwrap();
+        for player in self.players.iter() {
+            writeln!(text, "{} {}", player.player_id, player.shipyard).unwrap();
+        }
+        writeln!(text, "{} {}", self.map_size.0, self.map_size.1).unwrap();
+        for row in self.resource_map.rows() {
+            let cells: Vec<String> = row.iter().map(|halite| halite.to_string()).collect();
+            writeln!(text, "{}", cells.join(" ")).unwrap();
+        }
+        text
+    }
+    
+    /// The lines describing the current turn, which `Khala::update` parses. Every player gets
+    /// the same frame.
+    pub fn frame_text(&self) -> String {
+        let mut text = String::new();
+        writeln!(text, "{}", self.turn_number).unwrap();
+        for player in self.players.iter() {
+            let ships: Vec<&Ship> = self.ships
+                                        .values()
+                                        .filter(|ship| ship.owner_id == player.player_id)
+                                        .collect();
+            let dropoffs: Vec<&Structure> = self.structures.iter().filter(|structure| {
+                structure.owner_id == player.player_id &&
+                    structure.structure_type == StructureType::Dropoff
+            }).collect();
+            writeln!(text, "{} {} {} {}",
+                     player.player_id, ships.len(), dropoffs.len(), player.energy).unwrap();
+            for ship in ships {
+                writeln!(text, "{} {} {}", ship.ship_id, ship.position, ship.cargo).unwrap();
+            }
+            for dropoff in dropoffs {
+                writeln!(text, "{} {}", dropoff.structure_id, dropoff.position).unwrap();
+            }
+        }
+        writeln!(text, "{}", self.changed_cells.len()).unwrap();
+        for &position in self.changed_cells.iter() {
+            writeln!(text, "{} {}", position, self.halite_at(position)).unwrap();
+        }
+        text
+    }
+    
+    pub fn halite_at(&self, position: Position) -> HaliteAmount {
+        self.resource_map[position]
+    }
+    
+    /// Whether the ship mines and moves with the inspired ratios this turn.
+    pub fn is_inspired(&self, ship_id: ShipId) -> bool {
+        self.inspired.contains(&ship_id)
+    }
+    
+    /// Cells whose halite changed during the last turn.
+    pub fn changed_cells(&self) -> impl Iterator<Item = Position> + '_ {
+        self.changed_cells.iter().cloned()
+    }
+    
+    /// Spawns, constructions and collisions of the last turn, in the order they happened.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+    
+    fn set_halite_at(&mut self, position: Position, halite: HaliteAmount) {
+        self.resource_map[position] = halite;
+        self.changed_cells.insert(position);
+    }
+    
+    fn structure_at(&self, position: Position) -> Option<&Structure> {
+        self.structures.iter().find(|structure| structure.position == position)
+    }
+    
+    /// Removes a player from the game, e.g. because its bot crashed or timed out.
+    pub fn kick(&mut self, player_id: PlayerId, reason: String) {
+        let player = &mut self.players[player_id as usize];
+        if player.error.is_none() {
+            player.error = Some(reason);
+        }
+    }
+    
+    /// Plays one turn. `commands[player_id]` holds the commands that player sent for the current
+    /// frame as text, one `Command` per string or whole command lines. Players that send an invalid
+    /// set of commands are removed from the game.
+    pub fn process_turn(&mut self, commands: &[Vec<CommandString>]) -> Vec<CommandError> {
+        self.changed_cells.clear();
+        self.events.clear();
+        let mut errors = Vec::new();
+        
+        let mut orders: Vec<Vec<Command>> = vec![Vec::new(); self.players.len()];
+        for (player_id, player_orders) in orders.iter_mut().enumerate() {
+            if !self.players[player_id].is_alive() {
+                continue;
+            }
+            let player_commands = commands.get(player_id).map(|c| c.join(" ")).unwrap_or_default();
+            match Command::parse_line(&player_commands)
+                .and_then(|parsed| self.validate_orders(player_id as PlayerId, &parsed).map(|_| parsed)) {
+                Ok(parsed) => *player_orders = parsed,
+                Err(reason) => {
+                    self.kick(player_id as PlayerId, reason.clone());
+                    errors.push(CommandError {
+                        player_id: player_id as PlayerId,
+                        reason,
+                    });
+                }
+            }
+        }
+        
+        // dropoffs are built before anything moves
+        for (player_id, player_orders) in orders.iter().enumerate() {
+            for order in player_orders.iter() {
+                if let Command::Construct(ship_id) = *order {
+                    let ship = self.ships.remove(&ship_id).unwrap();
+                    let cell = self.halite_at(ship.position);
+                    let player = &mut self.players[player_id];
+                    player.energy = player.energy + ship.cargo + cell - self.constants.dropoff_cost;
+                    self.structures.push(Structure {
+                        owner_id: player_id as PlayerId,
+                        structure_id: self.next_dropoff_id,
+                        position: ship.position,
+                        structure_type: StructureType::Dropoff,
+                    });
+                    self.events.push(GameEvent::Construct {
+                        owner_id: player_id as PlayerId,
+                        structure_id: self.next_dropoff_id,
+                        position: ship.position,
+                    });
+                    self.next_dropoff_id += 1;
+                    self.set_halite_at(ship.position, 0);
+                }
+            }
+        }
+        
+        let mut moved = HashSet::new();
+        for player_orders in orders.iter() {
+            for order in player_orders.iter() {
+                if let Command::Move(ship_id, direction) = *order {
+                    if direction == Direction::Still {
+                        continue;
+                    }
+                    let ratio = if self.inspired.contains(&ship_id) {
+                        self.constants.inspired_move_cost_ratio
+                    } else {
+                        self.constants.move_cost_ratio
+                    };
+                    let position = self.ships[&ship_id].position;
+                    let cost = self.halite_at(position) / ratio;
+                    let destination = position.offset(direction, &self.map_size);
+                    let ship = self.ships.get_mut(&ship_id).unwrap();
+                    if ship.cargo >= cost {
+                        ship.cargo -= cost;
+                        ship.position = destination;
+                        moved.insert(ship_id);
+                    }
+                }
+            }
+        }
+        
+        let mut spawned = HashSet::new();
+        for (player_id, player_orders) in orders.iter().enumerate() {
+            if player_orders.contains(&Command::Spawn) {
+                let player = &mut self.players[player_id];
+                player.energy -= self.constants.new_entity_energy_cost;
+                let ship = Ship {
+                    owner_id: player_id as PlayerId,
+                    ship_id: self.next_ship_id,
+                    cargo: 0,
+                    position: player.shipyard,
+                };
+                self.ships.insert(ship.ship_id, ship);
+                spawned.insert(ship.ship_id);
+                self.events.push(GameEvent::Spawn {
+                    owner_id: ship.owner_id,
+                    ship_id: ship.ship_id,
+                    position: ship.position,
+                });
+                self.next_ship_id += 1;
+            }
+        }
+        
+        self.resolve_collisions();
+        
+        // deposit into own structures, mine everywhere else
+        let ship_ids: Vec<ShipId> = self.ships.keys().cloned().collect();
+        for ship_id in ship_ids {
+            let ship = self.ships[&ship_id];
+            let own_structure = self.structure_at(ship.position)
+                                    .is_some_and(|structure| structure.owner_id == ship.owner_id);
+            if own_structure {
+                let player = &mut self.players[ship.owner_id as usize];
+                player.energy += ship.cargo;
+                player.deposited += ship.cargo;
+                self.ships.get_mut(&ship_id).unwrap().cargo = 0;
+            } else if !moved.contains(&ship_id) && !spawned.contains(&ship_id) {
+                self.mine(ship_id);
+            }
+        }
+        
+        self.update_inspiration();
+        
+        let turn_number = self.turn_number;
+        for player in self.players.iter_mut().filter(|player| player.is_alive()) {
+            player.last_turn_alive = turn_number;
+        }
+        self.turn_number += 1;
+        
+        let alive = self.players.iter().filter(|player| player.is_alive()).count();
+        let can_act = self.players.iter().any(|player| {
+            player.is_alive() && (player.energy >= self.constants.new_entity_energy_cost ||
+                self.ships.values().any(|ship| ship.owner_id == player.player_id))
+        });
+        if alive == 0 || (self.players.len() > 1 && alive <= 1) || !can_act {
+            self.finished = true;
+        }
+        
+        errors
+    }
+    
+    fn validate_orders(&self, player_id: PlayerId, orders: &[Command]) -> Result<(), String> {
+        let mut commanded = HashSet::new();
+        let mut spawns = 0;
+        let mut cost: i64 = 0;
+        for order in orders.iter() {
+            let ship_id = match *order {
+                Command::Spawn => {
+                    spawns += 1;
+                    if spawns > 1 {
+                        return Err("more than one spawn command".to_string());
+                    }
+                    cost += self.constants.new_entity_energy_cost as i64;
+                    continue;
+                }
+                Command::Construct(ship_id) | Command::Move(ship_id, _) => ship_id,
+            };
+            let ship = match self.ships.get(&ship_id) {
+                Some(ship) if ship.owner_id == player_id => ship,
+                Some(_) => return Err(format!("ship {} belongs to another player", ship_id)),
+                None => return Err(format!("ship {} does not exist", ship_id)),
+            };
+            if !commanded.insert(ship_id) {
+                return Err(format!("ship {} was given more than one command", ship_id));
+            }
+            if let Command::Construct(_) = *order {
+                if self.structure_at(ship.position).is_some() {
+                    return Err(format!("ship {} cannot build on top of a structure", ship_id));
+                }
+                cost += self.constants.dropoff_cost as i64 -
+                    ship.cargo as i64 - self.halite_at(ship.position) as i64;
+            }
+        }
+        if cost > self.players[player_id as usize].energy as i64 {
+            return Err(format!("not enough halite to pay for {} halite of commands", cost));
+        }
+        Ok(())
+    }
+    
+    /// Every cell with more than one ship on it destroys all of them. Their cargo goes to the
+    /// owner of a structure on that cell, or is dropped into the sea otherwise.
+    fn resolve_collisions(&mut self) {
+        let mut by_position: HashMap<Position, Vec<ShipId>> = HashMap::new();
+        for ship in self.ships.values() {
+            by_position.entry(ship.position).or_default().push(ship.ship_id);
+        }
+        let mut collisions: Vec<(Position, Vec<ShipId>)> =
+            by_position.into_iter().filter(|(_, ships)| ships.len() > 1).collect();
+        collisions.sort();
+        for (position, ship_ids) in collisions {
+            let cargo: HaliteAmount = ship_ids.iter()
+                                              .map(|ship_id| self.ships.remove(ship_id).unwrap().cargo)
+                                              .sum();
+            match self.structure_at(position).map(|structure| structure.owner_id) {
+                Some(owner_id) => self.players[owner_id as usize].energy += cargo,
+                None => {
+                    let halite = self.halite_at(position);
+                    self.set_halite_at(position, halite + cargo);
+                }
+            }
+            self.events.push(GameEvent::Shipwreck {
+                position,
+                ships: ship_ids,
+            });
+        }
+    }
+    
+    fn mine(&mut self, ship_id: ShipId) {
+        let ship = self.ships[&ship_id];
+        let inspired = self.inspired.contains(&ship_id);
+        let ratio = if inspired {
+            self.constants.inspired_extract_ratio
+        } else {
+            self.constants.extract_ratio
+        };
+        let cell = self.halite_at(ship.position);
+        let capacity = self.constants.max_energy.saturating_sub(ship.cargo);
+        let extracted = cell.div_ceil(ratio).min(capacity);
+        if extracted == 0 {
+            return;
+        }
+        let bonus = if inspired {
+            ((extracted as f64 * self.constants.inspired_bonus_multiplier) as HaliteAmount)
+                .min(capacity - extracted)
+        } else {
+            0
+        };
+        self.set_halite_at(ship.position, cell - extracted);
+        self.ships.get_mut(&ship_id).unwrap().cargo += extracted + bonus;
+    }
+    
+    /// A ship is inspired for the next turn when enough enemy ships are close to it.
+    fn update_inspiration(&mut self) {
+        self.inspired.clear();
+        if !self.constants.inspiration_enabled {
+            return;
+        }
+        for ship in self.ships.values() {
+            let enemies = self.ships.values().filter(|other| {
+                other.owner_id != ship.owner_id &&
+                    ship.position.distance(&other.position, &self.map_size) <=
+                        self.constants.inspiration_radius
+            }).count() as u32;
+            if enemies >= self.constants.inspiration_ship_count {
+                self.inspired.insert(ship.ship_id);
+            }
+        }
+    }
+    
+    /// Final standings: players who stayed in the game longer rank higher, then more halite.
+    pub fn results(&self) -> Vec<PlayerResult> {
+        let mut order: Vec<&PlayerState> = self.players.iter().collect();
+        order.sort_by(|a, b| {
+            b.last_turn_alive.cmp(&a.last_turn_alive)
+             .then(b.energy.cmp(&a.energy))
+             .then(a.player_id.cmp(&b.player_id))
+        });
+        let mut results: Vec<PlayerResult> = order.iter().enumerate().map(|(index, player)| {
+            PlayerResult {
+                player_id: player.player_id,
+                rank: index as u32 + 1,
+                halite: player.energy,
+                last_turn_alive: player.last_turn_alive,
+                error: player.error.clone(),
+            }
+        }).collect();
+        results.sort_by_key(|result| result.player_id);
+        results
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Engine, GameConfig};
+    use crate::halite::game::Ship;
+    use crate::halite::position::Position;
+    
+    fn engine() -> Engine {
+        let mut config = GameConfig::new((8, 8), 2, 7);
+        config.constants.inspiration_enabled = false;
+        let mut engine = Engine::new(&config);
+        for cell in engine.resource_map.values_mut() {
+            *cell = 100;
+        }
+        engine
+    }
+    
+    fn add_ship(engine: &mut Engine, owner_id: u32, ship_id: u32, position: (i32, i32), cargo: u32) {
+        let position = position.into();
+        engine.ships.insert(ship_id, Ship { owner_id, ship_id, cargo, position });
+        engine.next_ship_id = engine.next_ship_id.max(ship_id + 1);
+    }
+    
+    #[test]
+    fn scales_turn_limit_with_map_size() {
+        assert_eq!(GameConfig::new((32, 32), 2, 0).constants.max_turns, 400);
+        assert_eq!(GameConfig::new((48, 48), 2, 0).constants.max_turns, 450);
+        assert_eq!(GameConfig::new((64, 64), 4, 0).constants.max_turns, 500);
+    }
+    
+    #[test]
+    fn generates_symmetric_maps() {
+        let engine = Engine::new(&GameConfig::new((16, 16), 4, 42));
+        for y in 0..16 {
+            for x in 0..16 {
+                assert_eq!(engine.resource_map[Position::new(x, y)], engine.resource_map[Position::new(15 - x, y)]);
+                assert_eq!(engine.resource_map[Position::new(x, y)], engine.resource_map[Position::new(x, 15 - y)]);
+            }
+        }
+        let shipyards: Vec<_> = engine.players.iter().map(|player| player.shipyard).collect();
+        assert_eq!(shipyards, vec![Position::new(4, 4), Position::new(11, 4), Position::new(4, 11), Position::new(11, 11)]);
+        assert!(engine.total_halite() > 0);
+    }
+    
+    #[test]
+    fn mines_moves_and_pays_move_cost() {
+        let mut engine = engine();
+        add_ship(&mut engine, 0, 0, (1, 1), 0);
+        add_ship(&mut engine, 0, 1, (2, 1), 50);
+        engine.process_turn(&[vec!["m 1 e".to_string()], vec![]]);
+        assert_eq!(engine.ships[&0].cargo, 25);
+        assert_eq!(engine.halite_at(Position::new(1, 1)), 75);
+        assert_eq!(engine.ships[&1].position, Position::new(3, 1));
+        assert_eq!(engine.ships[&1].cargo, 40);
+        assert!(engine.frame_text().contains("\n1 1 75\n"));
+    }
+    
+    #[test]
+    fn ships_without_enough_cargo_stay_and_mine() {
+        let mut engine = engine();
+        add_ship(&mut engine, 0, 0, (1, 1), 5);
+        engine.process_turn(&[vec!["m 0 n".to_string()], vec![]]);
+        assert_eq!(engine.ships[&0].position, Position::new(1, 1));
+        assert_eq!(engine.ships[&0].cargo, 30);
+    }
+    
+    #[test]
+    fn collisions_destroy_ships_and_drop_cargo() {
+        let mut engine = engine();
+        add_ship(&mut engine, 0, 0, (1, 1), 200);
+        add_ship(&mut engine, 1, 1, (3, 1), 300);
+        engine.process_turn(&[vec!["m 0 e".to_string()], vec!["m 1 w".to_string()]]);
+        assert!(engine.ships.is_empty());
+        assert_eq!(engine.halite_at(Position::new(2, 1)), 100 + 190 + 290);
+    }
+    
+    #[test]
+    fn spawns_deposits_and_builds_dropoffs() {
+        let mut engine = engine();
+        let shipyard = engine.players[0].shipyard;
+        add_ship(&mut engine, 0, 0, (shipyard.x + 1, shipyard.y), 500);
+        add_ship(&mut engine, 0, 1, (0, 0), 600);
+        engine.process_turn(&[vec!["g".to_string(), "m 0 w".to_string(), "c 1".to_string()], vec![]]);
+        // the spawned ship and the returning ship collide on the shipyard
+        assert!(!engine.ships.contains_key(&0));
+        assert_eq!(engine.players[0].energy, 5000 - 1000 + 490 + 600 + 100 - 4000);
+        assert_eq!(engine.structures.len(), 3);
+        assert_eq!(engine.halite_at(Position::new(0, 0)), 0);
+    }
+    
+    #[test]
+    fn invalid_commands_remove_the_player() {
+        let mut engine = engine();
+        add_ship(&mut engine, 1, 0, (1, 1), 0);
+        let errors = engine.process_turn(&[vec!["m 0 n".to_string()], vec![]]);
+        assert_eq!(errors.len(), 1);
+        assert!(!engine.players[0].is_alive());
+        assert!(engine.is_finished());
+        let results = engine.results();
+        assert_eq!(results[1].rank, 1);
+        assert_eq!(results[0].rank, 2);
+    }
+}
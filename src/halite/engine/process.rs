@@ -0,0 +1,230 @@
+//! Plays games between bot executables: every bot runs as a child process and talks to the
+//! `Engine` over its stdin and stdout, exactly as it would with the official engine.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use super::{Engine, GameConfig, GameResult};
+use super::super::game::CommandString;
+use super::super::replay::{Replay, ReplayRecorder};
+
+#[derive(Clone, Debug)]
+pub struct ProcessOptions {
+    /// how long a bot may take to answer the initial game state with its name
+    pub init_timeout: Option<Duration>,
+    /// how long a bot may take to answer a frame with its commands
+    pub turn_timeout: Option<Duration>,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> ProcessOptions {
+        ProcessOptions {
+            init_timeout: Some(Duration::from_secs(30)),
+            turn_timeout: Some(Duration::from_secs(2)),
+        }
+    }
+}
+
+/// A bot executable started through the shell, so commands like
+/// `"RUST_BACKTRACE=1 ./target/debug/my_bot"` work the same way they do in `run_game.sh`.
+pub struct BotProcess {
+    pub command: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    lines: Receiver<String>,
+}
+
+impl BotProcess {
+    pub fn spawn(command: &str) -> io::Result<BotProcess> {
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        let mut child = shell.arg(command)
+                             .stdin(Stdio::piped())
+                             .stdout(Stdio::piped())
+                             .stderr(Stdio::inherit())
+                             .spawn()?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        // a bot may block forever, so its output is read on a thread of its own
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() {
+                        break;
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(BotProcess {
+            command: command.to_string(),
+            child,
+            stdin,
+            lines,
+        })
+    }
+    
+    pub fn send(&mut self, text: &str) -> Result<(), String> {
+        match self.stdin.as_mut() {
+            Some(stdin) => stdin.write_all(text.as_bytes())
+                                .and_then(|_| stdin.flush())
+                                .map_err(|error| format!("could not write to bot: {}", error)),
+            None => Err("bot input is closed".to_string()),
+        }
+    }
+    
+    pub fn receive(&mut self, timeout: Option<Duration>) -> Result<String, String> {
+        match timeout {
+            Some(timeout) => self.lines.recv_timeout(timeout).map_err(|error| match error {
+                RecvTimeoutError::Timeout => "timed out".to_string(),
+                RecvTimeoutError::Disconnected => "exited".to_string(),
+            }),
+            None => self.lines.recv().map_err(|_| "exited".to_string()),
+        }
+    }
+    
+    /// Closes the bot's stdin, which tells a well behaved bot the game is over, and makes sure
+    /// the process is gone.
+    pub fn shutdown(mut self, grace: Duration) {
+        self.stdin = None;
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The outcome of a game between bot processes, the commands every player sent each turn and
+/// the replay of the game.
+#[derive(Clone, Debug)]
+pub struct ProcessGame {
+    pub config: GameConfig,
+    pub bot_commands: Vec<String>,
+    pub result: GameResult,
+    /// `turns[turn - 1][player_id]` is the command line that player sent
+    pub turns: Vec<Vec<CommandString>>,
+    pub replay: Replay,
+}
+
+/// Plays a complete game between the bot executables in `commands`, the first being player 0.
+/// Bots that crash, time out or send invalid commands are removed from the game.
+pub fn play_process_game(config: &GameConfig,
+                         commands: &[String],
+                         options: &ProcessOptions) -> io::Result<ProcessGame> {
+    assert_eq!(commands.len() as u32, config.num_players, "one bot per player is needed");
+    let mut engine = Engine::new(config);
+    let mut recorder = ReplayRecorder::new(&engine);
+    
+    let mut bots = Vec::new();
+    for command in commands.iter() {
+        bots.push(BotProcess::spawn(command)?);
+    }
+    
+    let mut bot_names = Vec::new();
+    for (player_id, bot) in bots.iter_mut().enumerate() {
+        let name = bot.send(&engine.init_text(player_id as u32))
+                      .and_then(|_| bot.receive(options.init_timeout));
+        match name {
+            Ok(name) => bot_names.push(name.trim().to_string()),
+            Err(reason) => {
+                engine.kick(player_id as u32, format!("initialization failed: {}", reason));
+                bot_names.push(String::new());
+            }
+        }
+    }
+    
+    let mut turns = Vec::new();
+    while !engine.is_finished() {
+        let frame = engine.frame_text();
+        // every bot gets the same amount of time, counted from when the frame was sent
+        let deadline = options.turn_timeout.map(|timeout| Instant::now() + timeout);
+        for (player_id, bot) in bots.iter_mut().enumerate() {
+            if engine.players[player_id].is_alive() {
+                if let Err(reason) = bot.send(&frame) {
+                    engine.kick(player_id as u32, reason);
+                }
+            }
+        }
+        let mut turn_commands = Vec::new();
+        for (player_id, bot) in bots.iter_mut().enumerate() {
+            if !engine.players[player_id].is_alive() {
+                turn_commands.push(String::new());
+                continue;
+            }
+            let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            match bot.receive(remaining) {
+                Ok(line) => turn_commands.push(line.trim().to_string()),
+                Err(reason) => {
+                    engine.kick(player_id as u32,
+                                format!("turn {}: {}", engine.turn_number, reason));
+                    turn_commands.push(String::new());
+                }
+            }
+        }
+        let per_player: Vec<Vec<CommandString>> =
+            turn_commands.iter().map(|line| vec![line.clone()]).collect();
+        recorder.process_turn(&mut engine, &per_player);
+        turns.push(turn_commands);
+    }
+    
+    for bot in bots {
+        bot.shutdown(Duration::from_millis(500));
+    }
+    
+    let players = engine.results();
+    Ok(ProcessGame {
+        config: config.clone(),
+        bot_commands: commands.to_vec(),
+        replay: recorder.finish(&bot_names, &players),
+        result: GameResult {
+            bot_names,
+            turns: turns.len() as u32,
+            players,
+        },
+        turns,
+    })
+}
+
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{play_process_game, ProcessOptions};
+    use crate::halite::engine::GameConfig;
+    use std::time::Duration;
+    
+    #[test]
+    fn kicks_bots_that_do_not_answer() {
+        let mut config = GameConfig::new((8, 8), 2, 1);
+        config.constants.max_turns = 5;
+        // answers with its name, then with an empty command line to everything it reads
+        let idle = "read line; echo idle; while read line; do echo; done".to_string();
+        let options = ProcessOptions {
+            init_timeout: Some(Duration::from_millis(500)),
+            turn_timeout: Some(Duration::from_millis(200)),
+        };
+        let game = play_process_game(&config, &[idle, "sleep 5".to_string()], &options).unwrap();
+        
+        assert_eq!(game.result.bot_names[0], "idle");
+        assert!(game.result.players[1].error.is_some());
+        assert_eq!(game.result.players[0].rank, 1);
+        // the game is over as soon as a single player is left
+        assert_eq!(game.result.turns, 1);
+        assert_eq!(game.turns, vec![vec![String::new(), String::new()]]);
+        assert_eq!(game.replay.turns(), 1);
+    }
+}
@@ -0,0 +1,374 @@
+//! Plays many games between bot versions and aggregates the results, so a strategy change can
+//! be judged on hundreds of games instead of one.
+
+use serde_json::{json, Value};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use super::process::{play_process_game, ProcessOptions};
+use super::{GameConfig, PlayerResult};
+
+/// One contestant, `label` is what shows up in the summary.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BotVersion {
+    pub label: String,
+    pub command: String,
+}
+
+impl BotVersion {
+    /// A bot labeled with its own command.
+    pub fn new(command: &str) -> BotVersion {
+        BotVersion {
+            label: command.to_string(),
+            command: command.to_string(),
+        }
+    }
+    
+    /// Parses `label=command`, split at the first `=`.
+    pub fn labeled(spec: &str) -> Result<BotVersion, String> {
+        match spec.find('=') {
+            Some(index) if index > 0 => Ok(BotVersion {
+                label: spec[..index].to_string(),
+                command: spec[index + 1..].to_string(),
+            }),
+            _ => Err(format!("expected <label>=<bot command>, got {:?}", spec)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentConfig {
+    pub games_per_pairing: u32,
+    pub map_sizes: Vec<u32>,
+    pub player_counts: Vec<u32>,
+    pub base_seed: u64,
+    pub jobs: usize,
+    pub options: ProcessOptions,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> TournamentConfig {
+        TournamentConfig {
+            games_per_pairing: 10,
+            map_sizes: vec![32, 40, 48, 56, 64],
+            player_counts: vec![2, 4],
+            base_seed: 0,
+            jobs: 1,
+            options: ProcessOptions::default(),
+        }
+    }
+}
+
+/// A scheduled game: `seats[player_id]` is an index into the bot versions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match {
+    pub index: usize,
+    pub seats: Vec<usize>,
+    pub map_size: u32,
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchResult {
+    pub game: Match,
+    pub turns: u32,
+    pub players: Vec<PlayerResult>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BotSummary {
+    pub label: String,
+    pub games: u32,
+    pub wins: u32,
+    pub mean_score: f64,
+    pub mean_rank: f64,
+    pub rating: f64,
+}
+
+impl BotSummary {
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 { 0.0 } else { self.wins as f64 / self.games as f64 }
+    }
+}
+
+/// Every pairing of bots plays `games_per_pairing` games on every map size and player count.
+/// Two player games cover every pair of bots, four player games every group of four bots, or
+/// every pair seated twice when there are fewer than four bots. Seats rotate between games and
+/// seeds only depend on `base_seed` and the position in the schedule.
+pub fn schedule(config: &TournamentConfig, num_bots: usize) -> Vec<Match> {
+    let mut groups: Vec<(u32, Vec<usize>)> = Vec::new();
+    for &players in config.player_counts.iter() {
+        if num_bots >= players as usize {
+            for group in combinations(num_bots, players as usize) {
+                groups.push((players, group));
+            }
+        } else {
+            for pair in combinations(num_bots, 2) {
+                let group = (0..players as usize).map(|seat| pair[seat % 2]).collect();
+                groups.push((players, group));
+            }
+        }
+    }
+    
+    let mut matches = Vec::new();
+    for (_, group) in groups.iter() {
+        for &map_size in config.map_sizes.iter() {
+            for game in 0..config.games_per_pairing as usize {
+                let mut seats = group.clone();
+                let rotation = game % seats.len();
+                seats.rotate_left(rotation);
+                let index = matches.len();
+                matches.push(Match {
+                    index,
+                    seats,
+                    map_size,
+                    seed: config.base_seed.wrapping_add(index as u64),
+                });
+            }
+        }
+    }
+    matches
+}
+
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    if n < k {
+        return Vec::new();
+    }
+    let mut result = Vec::new();
+    let mut current: Vec<usize> = (0..k).collect();
+    loop {
+        result.push(current.clone());
+        let mut i = k;
+        while i > 0 && current[i - 1] == n - k + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return result;
+        }
+        current[i - 1] += 1;
+        for j in i..k {
+            current[j] = current[j - 1] + 1;
+        }
+    }
+}
+
+pub const INITIAL_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+/// Multiplayer Elo: a game counts as one pairwise match between every two seats held by
+/// different bots, each weighted down by the number of opponents.
+pub fn update_ratings(ratings: &mut [f64], seats: &[usize], players: &[PlayerResult]) {
+    let mut deltas = vec![0.0; ratings.len()];
+    let opponents = (seats.len() - 1).max(1) as f64;
+    for (a, &bot_a) in seats.iter().enumerate() {
+        for (b, &bot_b) in seats.iter().enumerate() {
+            if bot_a == bot_b {
+                continue;
+            }
+            let expected = 1.0 / (1.0 + 10f64.powf((ratings[bot_b] - ratings[bot_a]) / 400.0));
+            let score = match players[a].rank.cmp(&players[b].rank) {
+                std::cmp::Ordering::Less => 1.0,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Greater => 0.0,
+            };
+            deltas[bot_a] += K_FACTOR / opponents * (score - expected);
+        }
+    }
+    for (rating, delta) in ratings.iter_mut().zip(deltas) {
+        *rating += delta;
+    }
+}
+
+/// Aggregates results, which must be in schedule order so the ratings are reproducible.
+/// Statistics are per seat, a bot seated twice in a four player game counts both seats.
+pub fn summarize(bots: &[BotVersion], results: &[MatchResult]) -> Vec<BotSummary> {
+    let mut ratings = vec![INITIAL_RATING; bots.len()];
+    let mut games = vec![0u32; bots.len()];
+    let mut wins = vec![0u32; bots.len()];
+    let mut scores = vec![0f64; bots.len()];
+    let mut ranks = vec![0f64; bots.len()];
+    for result in results.iter() {
+        for (seat, &bot) in result.game.seats.iter().enumerate() {
+            let player = &result.players[seat];
+            games[bot] += 1;
+            scores[bot] += player.halite as f64;
+            ranks[bot] += player.rank as f64;
+            if player.rank == 1 {
+                wins[bot] += 1;
+            }
+        }
+        update_ratings(&mut ratings, &result.game.seats, &result.players);
+    }
+    bots.iter().enumerate().map(|(bot, version)| {
+        let played = games[bot].max(1) as f64;
+        BotSummary {
+            label: version.label.clone(),
+            games: games[bot],
+            wins: wins[bot],
+            mean_score: scores[bot] / played,
+            mean_rank: ranks[bot] / played,
+            rating: ratings[bot],
+        }
+    }).collect()
+}
+
+/// Plays the whole schedule on `config.jobs` threads and returns the results in schedule order.
+/// `progress` is called once per finished game.
+pub fn run_tournament<F: FnMut(&MatchResult)>(config: &TournamentConfig,
+                                              bots: &[BotVersion],
+                                              mut progress: F) -> Vec<MatchResult> {
+    let matches = schedule(config, bots.len());
+    let queue = Arc::new(Mutex::new(matches.into_iter()));
+    let (sender, receiver) = mpsc::channel();
+    let mut workers = Vec::new();
+    for _ in 0..config.jobs.max(1) {
+        let queue = queue.clone();
+        let sender = sender.clone();
+        let options = config.options.clone();
+        let bots = bots.to_vec();
+        workers.push(thread::spawn(move || loop {
+            let game = match queue.lock().unwrap().next() {
+                Some(game) => game,
+                None => break,
+            };
+            let game_config = GameConfig::new((game.map_size, game.map_size),
+                                              game.seats.len() as u32, game.seed);
+            let commands: Vec<String> =
+                game.seats.iter().map(|&bot| bots[bot].command.clone()).collect();
+            let result = match play_process_game(&game_config, &commands, &options) {
+                Ok(played) => MatchResult {
+                    game,
+                    turns: played.result.turns,
+                    players: played.result.players,
+                },
+                // bots that cannot even be started lose every seat they hold
+                Err(error) => MatchResult {
+                    turns: 0,
+                    players: game.seats.iter().enumerate().map(|(seat, _)| PlayerResult {
+                        player_id: seat as u32,
+                        rank: game.seats.len() as u32,
+                        halite: 0,
+                        last_turn_alive: 0,
+                        error: Some(error.to_string()),
+                    }).collect(),
+                    game,
+                },
+            };
+            if sender.send(result).is_err() {
+                break;
+            }
+        }));
+    }
+    drop(sender);
+    
+    let mut results: Vec<MatchResult> = Vec::new();
+    for result in receiver {
+        progress(&result);
+        results.push(result);
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    results.sort_by_key(|result| result.game.index);
+    results
+}
+
+pub fn summary_csv(summaries: &[BotSummary]) -> String {
+    let mut csv = String::from("bot,games,wins,win_rate,mean_score,mean_rank,rating\n");
+    for summary in summaries.iter() {
+        csv.push_str(&format!("\"{}\",{},{},{:.4},{:.1},{:.3},{:.1}\n",
+                              summary.label.replace('"', "\"\""), summary.games, summary.wins,
+                              summary.win_rate(), summary.mean_score, summary.mean_rank,
+                              summary.rating));
+    }
+    csv
+}
+
+pub fn summary_json(bots: &[BotVersion], summaries: &[BotSummary], results: &[MatchResult]) -> Value {
+    json!({
+        "bots": summaries.iter().zip(bots.iter()).map(|(summary, bot)| json!({
+            "label": summary.label,
+            "command": bot.command,
+            "games": summary.games,
+            "wins": summary.wins,
+            "win_rate": summary.win_rate(),
+            "mean_score": summary.mean_score,
+            "mean_rank": summary.mean_rank,
+            "rating": summary.rating,
+        })).collect::<Vec<Value>>(),
+        "games": results.iter().map(|result| json!({
+            "seed": result.game.seed,
+            "map_size": result.game.map_size,
+            "turns": result.turns,
+            "players": result.game.seats.iter().zip(result.players.iter()).map(|(&bot, player)| json!({
+                "bot": bots[bot].label,
+                "rank": player.rank,
+                "halite": player.halite,
+                "error": player.error,
+            })).collect::<Vec<Value>>(),
+        })).collect::<Vec<Value>>(),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{schedule, summarize, BotVersion, Match, MatchResult, TournamentConfig};
+    use crate::halite::engine::PlayerResult;
+    
+    fn player(player_id: u32, rank: u32, halite: u32) -> PlayerResult {
+        PlayerResult { player_id, rank, halite, last_turn_alive: 400, error: None }
+    }
+    
+    #[test]
+    fn labels_only_when_asked_to() {
+        assert_eq!(BotVersion::labeled("v2=./bot --fast"), Ok(BotVersion {
+            label: "v2".to_string(),
+            command: "./bot --fast".to_string(),
+        }));
+        assert_eq!(BotVersion::labeled("BASELINE=RUST_BACKTRACE=1 ./bot").unwrap().label, "BASELINE");
+        assert_eq!(BotVersion::labeled("A=./a").unwrap().command, "./a");
+        assert!(BotVersion::labeled("./bot").is_err());
+        assert_eq!(BotVersion::new("RUST_BACKTRACE=1 ./bot").label, "RUST_BACKTRACE=1 ./bot");
+    }
+    
+    #[test]
+    fn schedules_every_pairing_reproducibly() {
+        let config = TournamentConfig {
+            games_per_pairing: 2,
+            map_sizes: vec![32, 64],
+            player_counts: vec![2, 4],
+            base_seed: 100,
+            ..TournamentConfig::default()
+        };
+        let matches = schedule(&config, 3);
+        // three pairs in two player games, three pairs seated twice in four player games
+        assert_eq!(matches.len(), (3 + 3) * 2 * 2);
+        assert_eq!(matches[0], Match { index: 0, seats: vec![0, 1], map_size: 32, seed: 100 });
+        assert_eq!(matches[1].seats, vec![1, 0]);
+        assert_eq!(matches[12].seats, vec![0, 1, 0, 1]);
+        assert_eq!(matches, schedule(&config, 3));
+        assert_eq!(schedule(&config, 5).iter().filter(|game| game.seats.len() == 4).count(),
+                   5 * 2 * 2);
+    }
+    
+    #[test]
+    fn summarizes_wins_scores_and_ratings() {
+        let bots = vec![BotVersion::labeled("a=./a").unwrap(), BotVersion::labeled("b=./b").unwrap()];
+        let results: Vec<MatchResult> = (0..4).map(|index| MatchResult {
+            game: Match { index, seats: vec![0, 1], map_size: 32, seed: index as u64 },
+            turns: 400,
+            players: vec![player(0, 1, 3000), player(1, 2, 1000)],
+        }).collect();
+        let summaries = summarize(&bots, &results);
+        assert_eq!(summaries[0].wins, 4);
+        assert_eq!(summaries[0].win_rate(), 1.0);
+        assert_eq!(summaries[1].mean_score, 1000.0);
+        assert_eq!(summaries[1].mean_rank, 2.0);
+        assert!(summaries[0].rating > 1500.0 && summaries[1].rating < 1500.0);
+        assert!((summaries[0].rating + summaries[1].rating - 3000.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,182 @@
+use std::{error, fmt, io};
+use super::game::{HaliteAmount, MapSize, PlayerId, ShipId};
+use super::position::Position;
+
+/// Everything that can go wrong while reading the engine's side of the protocol.
+///
+/// `line` is the 1-based number of the line read from the engine since the game started,
+/// `raw` is that line exactly as received (trailing newline stripped).
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io {
+        line: usize,
+        source: io::Error,
+    },
+    /// The engine closed its end of the pipe, which is what happens after the last turn.
+    UnexpectedEof {
+        line: usize,
+    },
+    TokenCount {
+        line: usize,
+        raw: String,
+        expected: usize,
+        found: usize,
+    },
+    BadInteger {
+        line: usize,
+        raw: String,
+        token: String,
+    },
+    /// The game constants line is not a valid JSON object.
+    BadJson {
+        line: usize,
+        raw: String,
+        reason: String,
+    },
+    /// A game constant the client relies on is missing or has an unusable value.
+    UnknownConstant {
+        line: usize,
+        raw: String,
+        name: String,
+    },
+    UnknownPlayer {
+        line: usize,
+        raw: String,
+        player_id: PlayerId,
+    },
+    OutOfBounds {
+        line: usize,
+        raw: String,
+        position: Position,
+        map_size: MapSize,
+    },
+}
+
+impl ProtocolError {
+    pub fn line(&self) -> usize {
+        match *self {
+            ProtocolError::Io { line, .. } |
+            ProtocolError::UnexpectedEof { line } |
+            ProtocolError::TokenCount { line, .. } |
+            ProtocolError::BadInteger { line, .. } |
+            ProtocolError::BadJson { line, .. } |
+            ProtocolError::UnknownConstant { line, .. } |
+            ProtocolError::UnknownPlayer { line, .. } |
+            ProtocolError::OutOfBounds { line, .. } => line,
+        }
+    }
+    
+    /// `true` when the engine simply stopped talking, i.e. the game is over.
+    pub fn is_eof(&self) -> bool {
+        matches!(self, ProtocolError::UnexpectedEof { .. })
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProtocolError::Io { line, source } =>
+                write!(f, "line {}: io error: {}", line, source),
+            ProtocolError::UnexpectedEof { line } =>
+                write!(f, "line {}: unexpected end of input", line),
+            ProtocolError::TokenCount { line, raw, expected, found } =>
+                write!(f, "line {}: expected {} tokens, found {}: {:?}", line, expected, found, raw),
+            ProtocolError::BadInteger { line, raw, token } =>
+                write!(f, "line {}: {:?} is not a valid integer: {:?}", line, token, raw),
+            ProtocolError::BadJson { line, raw, reason } =>
+                write!(f, "line {}: invalid constants json ({}): {:?}", line, reason, raw),
+            ProtocolError::UnknownConstant { line, raw, name } =>
+                write!(f, "line {}: missing or invalid constant {}: {:?}", line, name, raw),
+            ProtocolError::UnknownPlayer { line, raw, player_id } =>
+                write!(f, "line {}: unknown player {}: {:?}", line, player_id, raw),
+            ProtocolError::OutOfBounds { line, raw, position, map_size } =>
+                write!(f, "line {}: position {:?} is outside of a {}x{} map: {:?}",
+                       line, position, map_size.0, map_size.1, raw),
+        }
+    }
+}
+
+impl error::Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ProtocolError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Everything that can go wrong while reading or writing a `.hlt` replay.
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    /// The file decompressed fine but is not JSON.
+    BadJson(String),
+    /// A field the replay format requires is missing or has the wrong type, `path` is where
+    /// in the document it was expected, e.g. `full_frames[3].entities`.
+    BadField {
+        path: String,
+    },
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> ReplayError {
+        ReplayError::Io(error)
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "io error: {}", error),
+            ReplayError::BadJson(reason) => write!(f, "invalid replay json: {}", reason),
+            ReplayError::BadField { path } => write!(f, "missing or invalid replay field {}", path),
+        }
+    }
+}
+
+impl error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ReplayError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// A command `TurnCommands` refused because the engine would disqualify the bot for it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvalidCommand {
+    /// only one ship can be spawned per turn
+    SecondSpawn,
+    AlreadyCommanded(ShipId),
+    UnknownShip(ShipId),
+    ForeignShip {
+        ship_id: ShipId,
+        owner_id: PlayerId,
+    },
+    /// a dropoff cannot be built on top of another structure
+    StructureInTheWay(ShipId),
+    NotEnoughHalite {
+        needed: HaliteAmount,
+        available: HaliteAmount,
+    },
+}
+
+impl fmt::Display for InvalidCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidCommand::SecondSpawn => write!(f, "a ship was already spawned this turn"),
+            InvalidCommand::AlreadyCommanded(ship_id) =>
+                write!(f, "ship {} already has a command", ship_id),
+            InvalidCommand::UnknownShip(ship_id) => write!(f, "ship {} does not exist", ship_id),
+            InvalidCommand::ForeignShip { ship_id, owner_id } =>
+                write!(f, "ship {} belongs to player {}", ship_id, owner_id),
+            InvalidCommand::StructureInTheWay(ship_id) =>
+                write!(f, "ship {} stands on a structure", ship_id),
+            InvalidCommand::NotEnoughHalite { needed, available } =>
+                write!(f, "{} halite needed but only {} left", needed, available),
+        }
+    }
+}
+
+impl error::Error for InvalidCommand {}
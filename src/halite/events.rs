@@ -0,0 +1,224 @@
+//! What happened during the last turn, worked out by comparing the frame `Khala::update` just read
+//! with the one before. The engine does not say, so collisions are a best guess.
+
+use std::collections::HashMap;
+use std::slice;
+use super::constants::Constants;
+use super::game::{HaliteAmount, MapSize, PlayerId, Ship, ShipId, Structure, StructureId, StructureType};
+use super::position::{Position, RecursiveCellPosition};
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum TurnEvent {
+    ShipSpawned {
+        owner_id: PlayerId,
+        ship_id: ShipId,
+        position: Position,
+    },
+    /// A ship is gone without becoming a dropoff. `collided_with` are the other lost ships close
+    /// enough to have moved onto the same cell, empty when it is not clear what happened.
+    ShipLost {
+        ship: Ship,
+        collided_with: Vec<ShipId>,
+    },
+    /// Halite a player got in without spending or building, with the ships that unloaded.
+    HaliteDeposited {
+        owner_id: PlayerId,
+        amount: HaliteAmount,
+        ship_ids: Vec<ShipId>,
+    },
+    DropoffConstructed {
+        owner_id: PlayerId,
+        structure_id: StructureId,
+        position: Position,
+        /// the ship that was last seen on the new dropoff
+        ship_id: Option<ShipId>,
+    },
+    CellMined {
+        position: Position,
+        amount: HaliteAmount,
+    },
+    /// More halite on a cell than before, the cargo of sunk ships.
+    HaliteDropped {
+        position: Position,
+        amount: HaliteAmount,
+    },
+}
+
+/// The events of one turn: spawns, losses, deposits and new dropoffs, then the mined and dropped
+/// halite of each changed cell in the order the engine sent the cells.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct TurnEvents {
+    pub turn_number: u32,
+    events: Vec<TurnEvent>,
+}
+
+impl TurnEvents {
+    pub fn iter(&self) -> slice::Iter<'_, TurnEvent> {
+        self.events.iter()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+    
+    /// Ships, deposits and dropoffs of `owner_id`; cells belong to nobody and are left out.
+    pub fn of_player(&self, owner_id: PlayerId) -> impl Iterator<Item = &TurnEvent> {
+        self.iter().filter(move |event| match event {
+            TurnEvent::ShipSpawned { owner_id: owner, .. } |
+            TurnEvent::HaliteDeposited { owner_id: owner, .. } |
+            TurnEvent::DropoffConstructed { owner_id: owner, .. } => *owner == owner_id,
+            TurnEvent::ShipLost { ship, .. } => ship.owner_id == owner_id,
+            TurnEvent::CellMined { .. } | TurnEvent::HaliteDropped { .. } => false,
+        })
+    }
+}
+
+/// The part of a frame events are derived from.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Frame<'a> {
+    pub ships: &'a [Ship],
+    pub structures: &'a [Structure],
+    pub player_owned_halite: &'a [HaliteAmount],
+}
+
+/// `cells` are the updated cells with their halite before and after.
+pub(crate) fn diff(turn_number: u32, constants: &Constants, map_size: MapSize, before: Frame, after: Frame,
+                   cells: &[(Position, HaliteAmount, HaliteAmount)]) -> TurnEvents {
+    let mut events = Vec::new();
+    let old_ships: HashMap<ShipId, &Ship> = before.ships.iter().map(|ship| (ship.ship_id, ship)).collect();
+    let new_ships: HashMap<ShipId, &Ship> = after.ships.iter().map(|ship| (ship.ship_id, ship)).collect();
+    let new_dropoffs: Vec<&Structure> = after.structures.iter().filter(|structure| {
+        structure.structure_type == StructureType::Dropoff &&
+            !before.structures.iter().any(|old| old.structure_id == structure.structure_id)
+    }).collect();
+    let halite_before = |position: Position| {
+        cells.iter().find(|cell| cell.0 == position).map(|cell| cell.1).unwrap_or(0)
+    };
+    
+    // what each player paid for this turn, so that the rest of its income was deposited
+    let mut spent: HashMap<PlayerId, i64> = HashMap::new();
+    for ship in after.ships.iter().filter(|ship| !old_ships.contains_key(&ship.ship_id)) {
+        *spent.entry(ship.owner_id).or_insert(0) += constants.new_entity_energy_cost as i64;
+        events.push(TurnEvent::ShipSpawned {
+            owner_id: ship.owner_id,
+            ship_id: ship.ship_id,
+            position: ship.position,
+        });
+    }
+    
+    let mut builders = Vec::new();
+    let mut lost = Vec::new();
+    for ship in before.ships.iter().filter(|ship| !new_ships.contains_key(&ship.ship_id)) {
+        let built = new_dropoffs.iter().any(|dropoff| {
+            dropoff.owner_id == ship.owner_id && dropoff.position == ship.position
+        });
+        if built {
+            let cost = constants.dropoff_cost as i64 - ship.cargo as i64 - halite_before(ship.position) as i64;
+            *spent.entry(ship.owner_id).or_insert(0) += cost;
+            builders.push(ship);
+        } else {
+            lost.push(ship);
+        }
+    }
+    for ship in lost.iter() {
+        let collided_with = lost.iter()
+                                .filter(|other| other.ship_id != ship.ship_id &&
+                                    other.position.distance(&ship.position, &map_size) <= 2)
+                                .map(|other| other.ship_id)
+                                .collect();
+        events.push(TurnEvent::ShipLost { ship: **ship, collided_with });
+    }
+    
+    let incomes = before.player_owned_halite.iter().zip(after.player_owned_halite.iter());
+    for (owner_id, (&old, &new)) in incomes.enumerate() {
+        let owner_id = owner_id as PlayerId;
+        let amount = new as i64 - old as i64 + spent.get(&owner_id).cloned().unwrap_or(0);
+        if amount <= 0 {
+            continue;
+        }
+        let on_own_structure = |ship: &Ship| after.structures.iter().any(|structure| {
+            structure.owner_id == ship.owner_id && structure.position == ship.position
+        });
+        let ship_ids = after.ships
+                            .iter()
+                            .filter(|ship| ship.owner_id == owner_id && on_own_structure(ship))
+                            .filter(|ship| old_ships.get(&ship.ship_id).is_some_and(|old| old.cargo > 0))
+                            .map(|ship| ship.ship_id)
+                            .collect();
+        events.push(TurnEvent::HaliteDeposited {
+            owner_id,
+            amount: amount as HaliteAmount,
+            ship_ids,
+        });
+    }
+    
+    for dropoff in new_dropoffs.iter() {
+        events.push(TurnEvent::DropoffConstructed {
+            owner_id: dropoff.owner_id,
+            structure_id: dropoff.structure_id,
+            position: dropoff.position,
+            ship_id: builders.iter()
+                             .find(|ship| ship.position == dropoff.position)
+                             .map(|ship| ship.ship_id),
+        });
+    }
+    
+    for &(position, old, new) in cells {
+        if new_dropoffs.iter().any(|dropoff| dropoff.position == position) {
+            continue;
+        }
+        if new < old {
+            events.push(TurnEvent::CellMined { position, amount: old - new });
+        } else if new > old {
+            events.push(TurnEvent::HaliteDropped { position, amount: new - old });
+        }
+    }
+    TurnEvents { turn_number, events }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::TurnEvent;
+    use crate::halite::game::khala_from;
+    use crate::halite::position::Position;
+    use crate::halite::update::Update;
+    
+    // on turn 2 ships 0 and 2 sink at (1, 1), ship 1 unloads 300 less a move of 10, ship 5 turns
+    // into dropoff 7, player 1 spawns ship 4 and ship 6 mines
+    const GAME: &str = "{}\n2 0\n0 0 0\n1 3 3\n4 4\n\
+                        100 100 100 100\n100 100 100 100\n100 100 100 100\n100 100 100 100\n\
+                        1\n0 3 0 5000\n0 1 1 0\n1 0 1 300\n6 2 2 0\n1 2 0 5000\n2 2 1 50\n5 3 0 200\n0\n\
+                        2\n0 2 0 5290\n1 0 0 0\n6 2 2 25\n1 1 1 300\n4 3 3 0\n7 3 0\n\
+                        3\n1 1 150\n3 0 0\n2 2 75\n";
+    
+    #[test]
+    fn tells_what_happened_during_the_turn() {
+        let mut khala = khala_from(GAME);
+        khala.update().unwrap();
+        khala.update().unwrap();
+        let events: Vec<TurnEvent> = khala.events.iter().cloned().collect();
+        let lost = |ship_id: u32| khala.events.iter().find_map(|event| match event {
+            TurnEvent::ShipLost { ship, collided_with } if ship.ship_id == ship_id => Some(collided_with.clone()),
+            _ => None,
+        });
+        assert_eq!(events[0], TurnEvent::ShipSpawned { owner_id: 1, ship_id: 4, position: Position::new(3, 3) });
+        assert_eq!((lost(0), lost(2)), (Some(vec![2]), Some(vec![0])));
+        assert_eq!(events[3], TurnEvent::HaliteDeposited { owner_id: 0, amount: 290, ship_ids: vec![1] });
+        assert_eq!(events[4], TurnEvent::DropoffConstructed {
+            owner_id: 1,
+            structure_id: 7,
+            position: Position::new(3, 0),
+            ship_id: Some(5),
+        });
+        assert_eq!(&events[5..], &[
+            TurnEvent::HaliteDropped { position: Position::new(1, 1), amount: 50 },
+            TurnEvent::CellMined { position: Position::new(2, 2), amount: 25 },
+        ]);
+        assert_eq!(khala.events.of_player(1).count(), 3);
+    }
+}
@@ -0,0 +1,135 @@
+//! Per-cell notes for the Fluorine replay viewer, which shows them when the cell is clicked and
+//! paints the cell with the note's color. Load the saved file next to the replay.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use serde_json::{json, Value};
+use super::game::{Khala, ShipId};
+use super::position::Position;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct FlogEntry {
+    /// the turn as in `Khala::turn_number`
+    pub turn: u32,
+    pub position: Position,
+    pub message: String,
+    /// any CSS color, e.g. `#ff0000`
+    pub color: Option<String>,
+}
+
+impl FlogEntry {
+    /// Fluorine counts frames from 0 and shows on frame `t` the moves decided on turn `t + 1`.
+    pub fn to_json(&self) -> Value {
+        let mut entry = json!({
+            "t": self.turn.saturating_sub(1),
+            "x": self.position.x,
+            "y": self.position.y,
+            "msg": self.message,
+        });
+        if let Some(color) = &self.color {
+            entry["color"] = json!(color);
+        }
+        entry
+    }
+}
+
+/// The notes of a game, one per cell and turn.
+#[derive(Clone, Debug, Default)]
+pub struct Flog {
+    entries: BTreeMap<(u32, Position), FlogEntry>,
+}
+
+impl Flog {
+    pub fn new() -> Flog {
+        Flog::default()
+    }
+    
+    /// Adds `message` to the note of `position` on `turn`, on a line of its own. A color
+    /// replaces the one the note had.
+    pub fn annotate<S: Into<String>>(&mut self, turn: u32, position: Position, message: S, color: Option<&str>) {
+        let message = message.into();
+        let entry = self.entries.entry((turn, position)).or_insert_with(|| FlogEntry {
+            turn,
+            position,
+            message: String::new(),
+            color: None,
+        });
+        if !entry.message.is_empty() {
+            entry.message.push('\n');
+        }
+        entry.message.push_str(&message);
+        if let Some(color) = color {
+            entry.color = Some(color.to_string());
+        }
+    }
+    
+    /// Notes `message` on the current turn of `khala`.
+    pub fn tag<R, W, S: Into<String>>(&mut self, khala: &Khala<R, W>, position: Position, message: S) {
+        self.annotate(khala.turn_number, position, message, None);
+    }
+    
+    pub fn tag_colored<R, W, S: Into<String>>(&mut self, khala: &Khala<R, W>, position: Position, message: S,
+                                             color: &str) {
+        self.annotate(khala.turn_number, position, message, Some(color));
+    }
+    
+    /// Colors every cell of a ship's planned path, e.g. the `cells` of a `Path` or `Plan`.
+    pub fn tag_path<R, W>(&mut self, khala: &Khala<R, W>, ship_id: ShipId, cells: &[Position], color: &str) {
+        for (step, &position) in cells.iter().enumerate() {
+            self.tag_colored(khala, position, format!("ship {} path step {}", ship_id, step + 1), color);
+        }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    pub fn entries(&self) -> impl Iterator<Item = &FlogEntry> {
+        self.entries.values()
+    }
+    
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.entries().map(FlogEntry::to_json).collect())
+    }
+    
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        serde_json::to_writer(&mut writer, &self.to_json())?;
+        writer.flush()
+    }
+    
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Flog;
+    use crate::halite::position::Position;
+    use serde_json::json;
+    
+    #[test]
+    fn merges_notes_on_the_same_cell_and_turn() {
+        let mut flog = Flog::new();
+        flog.annotate(3, Position::new(1, 2), "ship 4 target", Some("#00ff00"));
+        flog.annotate(3, Position::new(1, 2), "ship 5 target", None);
+        flog.annotate(4, Position::new(1, 2), "mining", None);
+        assert_eq!(flog.len(), 2);
+        assert_eq!(flog.to_json(), json!([
+            { "t": 2, "x": 1, "y": 2, "msg": "ship 4 target\nship 5 target", "color": "#00ff00" },
+            { "t": 3, "x": 1, "y": 2, "msg": "mining" },
+        ]));
+        
+        let mut written = Vec::new();
+        flog.write_to(&mut written).unwrap();
+        let read: serde_json::Value = serde_json::from_slice(&written).unwrap();
+        assert_eq!(read, flog.to_json());
+    }
+}
@@ -1,10 +1,13 @@
-use super::input::tokenize;
-use super::log::{Log, Logger};
+use super::command::TurnCommands;
+use super::constants::Constants;
+use super::error::ProtocolError;
+use super::events::{self, Frame, TurnEvents};
+use super::grid::Grid;
+use super::input::{FrameReader, Line, LineContext};
+use super::log::{Level, Log, LogConfig, Logger, Record};
 use super::update::Update;
-use std::io::{self, Read, Write, stdin, stdout, Stdin, Stdout};
-use std::collections::HashMap;
+use std::io::{BufRead, Write, stdin, stdout, StdinLock, Stdout};
 use super::position::Position;
-use std::{fmt, convert};
 
 pub type ShipId = u32;
 pub type PlayerId = u32;
@@ -14,327 +17,377 @@ pub type HaliteAmount = u32;
 pub type CommandString = String;
 
 
-pub trait FourDirection {
-    const EAST: u8;
-    const WEST: u8;
-    const SOUTH: u8;
-    const NORTH: u8;
-}
-
-pub struct Direction {
-    d: u8
-}
-
-impl FourDirection for Direction {
-    const EAST: u8 = 0x00;
-    const WEST: u8 = 0x80;
-    const SOUTH: u8 = 0xc0;
-    const NORTH: u8 = 0x40;
-}
-
-// fmt::Display is for default formatter, right?
-// yes
-impl std::fmt::Display for Direction {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.d {
-            Direction::EAST => f.write_str(&"e"),
-            Direction::WEST => f.write_str(&"w"),
-            Direction::SOUTH => f.write_str(&"s"),
-            Direction::NORTH => f.write_str(&"n"),
-            _ => panic!("invalid direction")
-        }
-    }
-}
-
-impl convert::Into<Direction> for u8 {
-    fn into(self) -> Direction {
-        Direction {
-            d: self,
-        }
-    }
-}
-
-impl convert::Into<u8> for Direction {
-    fn into(self) -> u8 {
-        self.d
-    }
-}
-
+/// The client side of the game, reading engine frames from `R` and writing commands into `W`.
+/// The defaults are the transport of a real game, stdin and stdout.
 #[derive(Debug)]
-pub struct Khala {
+pub struct Khala<R = StdinLock<'static>, W = Stdout> {
     map_size: MapSize,
-    pub game_constants: HashMap<String, String>,
+    pub game_constants: Constants,
     
-    pub resource_map: Vec<Vec<u32>>,
+    pub resource_map: Grid<HaliteAmount>,
     pub ships: Vec<Ship>,
     pub structures: Vec<Structure>,
+    /// indexes into `ships`, one list per player id
+    pub ships_by_owner: Vec<Vec<usize>>,
+    /// indexes into `structures` for dropoffs only, one list per player id
+    pub dropoffs_by_owner: Vec<Vec<usize>>,
     pub player_owned_halite: Vec<HaliteAmount>,
+    /// what changed between the last two frames
+    pub events: TurnEvents,
     
     pub num_players: u32,
     pub my_id: PlayerId,
     pub turn_number: u32,
     logger: Logger,
+    frames: FrameReader,
+    /// the cells of the last frame with their old and new halite, kept to reuse its buffer
+    cells: Vec<(Position, HaliteAmount, HaliteAmount)>,
+    reader: R,
+    writer: W,
 }
 
 macro_rules! read_line_and_tokenize {
-    ($buf: ident, $tokens: ident, $logger: ident) => {
-        let mut $buf = String::new();
-        stdin().read_line(&mut $buf).unwrap();
-        let mut $tokens = tokenize(&mut $buf);
-        $logger.log(
-            format!(
-                "\
-                [Information] read_line_and_tokenize invoke at: {}::{} \n  \
-                Read from stdin: \n    \
-                buf: \n      \
-                {}    \
-                tokens: \n      \
-                {:?}\
-                \n",file!(), line!(), $buf, $tokens));
+    ($line: ident, $reader: expr, $line_number: expr, $logger: ident) => {
+        let $line = Line::read(&mut $reader, &mut $line_number)?;
+        if $logger.enabled(Level::Trace) {
+            $logger.trace(format!("line {}: {:?}", $line.number, $line.raw));
+        }
     };
     
-    ($buf: ident, $tokens: ident) => {
-        let mut $buf = String::new();
-        stdin().read_line(&mut $buf).unwrap();
-        let mut $tokens = tokenize(&mut $buf);
+    ($line: ident, $reader: expr, $line_number: expr) => {
+        let $line = Line::read(&mut $reader, &mut $line_number)?;
+    }
+}
+
+fn check_player<L: LineContext>(line: &L, player_id: PlayerId, num_players: u32) -> Result<PlayerId, ProtocolError> {
+    if player_id < num_players {
+        Ok(player_id)
+    } else {
+        Err(ProtocolError::UnknownPlayer {
+            line: line.line_number(),
+            raw: line.raw_line(),
+            player_id,
+        })
+    }
+}
+
+fn check_position<L: LineContext>(line: &L, x: u32, y: u32, map_size: MapSize) -> Result<Position, ProtocolError> {
+    let position = Position::from((x, y));
+    if x < map_size.0 && y < map_size.1 {
+        Ok(position)
+    } else {
+        Err(ProtocolError::OutOfBounds {
+            line: line.line_number(),
+            raw: line.raw_line(),
+            position,
+            map_size,
+        })
     }
 }
 
 impl Khala {
-    pub fn read_from_stdin() -> Khala {
-        let mut init_logger = Logger::new(env!("CARGO_MANIFEST_DIR"), "khala_init.log");
+    pub fn read_from_stdin() -> Result<Khala, ProtocolError> {
+        Khala::read_from(stdin().lock(), stdout())
+    }
+    
+    pub fn read_from_stdin_with_log(log_config: &LogConfig) -> Result<Khala, ProtocolError> {
+        Khala::read_with_log(stdin().lock(), stdout(), log_config)
+    }
+}
+
+/// Lookups that do not need the transport.
+impl<R, W> Khala<R, W> {
+    pub fn ships_of(&self, owner_id: PlayerId) -> impl Iterator<Item = &Ship> {
+        self.ships_by_owner
+            .get(owner_id as usize)
+            .into_iter()
+            .flatten()
+            .map(move |&index| &self.ships[index])
+    }
+    
+    pub fn dropoffs_of(&self, owner_id: PlayerId) -> impl Iterator<Item = &Structure> {
+        self.dropoffs_by_owner
+            .get(owner_id as usize)
+            .into_iter()
+            .flatten()
+            .map(move |&index| &self.structures[index])
+    }
+    
+    pub fn shipyard_of(&self, owner_id: PlayerId) -> Option<&Structure> {
+        self.structures.iter().find(|structure| {
+            structure.structure_type == StructureType::Shipyard &&
+                structure.owner_id == owner_id
+        })
+    }
+    
+    /// The shipyard and every dropoff of `owner_id`, i.e. everywhere its ships can unload.
+    pub fn structures_of(&self, owner_id: PlayerId) -> impl Iterator<Item = &Structure> {
+        self.shipyard_of(owner_id).into_iter().chain(self.dropoffs_of(owner_id))
+    }
+}
+
+impl<R: BufRead, W: Write> Khala<R, W> {
+    /// Reads the initial game state from `reader`; commands will be written into `writer`.
+    /// Logging is set up from the environment, see `LogConfig::from_env`.
+    pub fn read_from(reader: R, writer: W) -> Result<Khala<R, W>, ProtocolError> {
+        Khala::read_with_log(reader, writer, &LogConfig::from_env())
+    }
+    
+    /// `read_from` logging into `khala_bot_<my_id>.log` as `log_config` says.
+    pub fn read_with_log(mut reader: R, writer: W, log_config: &LogConfig) -> Result<Khala<R, W>, ProtocolError> {
+        let mut line_number = 0;
         
-        let game_constants = {
-            let buf = &mut String::new();
-            let result = stdin().read_line(buf);
-            match result {
-                Ok(_) => (),
-                Err(_) => {
-                    panic!("cannot read stdin");
-                }
-            }
-            
-            let token_iter = buf
-                .split(|c| " {},:\"\r\n".contains(c))
-                .filter(|x| !x.is_empty());
-            
-            let tokens: Vec<&str> = token_iter.collect();
-            init_logger.log(format!("Read from stdin: \nbuf: {}, \ntokens: {:#?}", buf, tokens));
-            if (tokens.len() % 2) != 0 {
-                init_logger.log(
-                    "Error: constants: expected even total number of key and value tokens from server.");
-            }
-            let mut map = HashMap::new();
-            init_logger.log(format!("Resolve as a HashMap: {:#?}", map));
-            for i in (0..tokens.len()).step_by(2) {
-                map.insert(tokens[i].to_string(), tokens[i + 1].to_string());
-            }
-            map
-        };
+        let constants_line = Line::read(&mut reader, &mut line_number)?;
+        let game_constants = Constants::from_line(&constants_line)?;
         
         let (num_players, my_id): (u32, u32) = {
-            read_line_and_tokenize!(buf, tokens, init_logger);
-            (tokens[0].parse().unwrap(),
-             tokens[1].parse().unwrap())
+            read_line_and_tokenize!(line, reader, line_number);
+            line.expect_tokens(2)?;
+            let num_players = line.integer(0)?;
+            (num_players, check_player(&line, line.integer(1)?, num_players)?)
         };
         
-        init_logger.log(format!("Resolve as num_players: {}, my_id: {:#?}", num_players, my_id));
-        
-        let mut logger =
-            Logger::new(env!("CARGO_MANIFEST_DIR"), format!("khala_bot_{}.log", my_id));
+        let mut logger = log_config.logger(&format!("khala_bot_{}.log", my_id));
+        if logger.enabled(Level::Trace) {
+            logger.trace(format!("line {}: {:?}", constants_line.number, constants_line.raw));
+        }
+        logger.debug(format!("constants: {:?}", game_constants));
+        logger.debug(format!("num_players: {}, my_id: {}", num_players, my_id));
         
-        let structures = {
+        let (structures, shipyard_lines) = {
             let mut structures = Vec::<Structure>::new();
+            let mut lines = Vec::<Line>::new();
             for player_id in 0..num_players {
-                structures.push({
-                    read_line_and_tokenize!(buf, tokens, logger);
-                    Structure {
-                        structure_id: (0x4000 + player_id) as StructureId,
-                        owner_id: tokens[0].parse().unwrap(),
-                        position: (tokens[1].parse().unwrap(), tokens[2].parse().unwrap()),
-                        structure_type: StructureType::Shipyard,
-                    }
+                read_line_and_tokenize!(line, reader, line_number, logger);
+                line.expect_tokens(3)?;
+                structures.push(Structure {
+                    structure_id: (0x4000 + player_id) as StructureId,
+                    owner_id: check_player(&line, line.integer(0)?, num_players)?,
+                    position: Position::from((line.integer(1)?, line.integer(2)?)),
+                    structure_type: StructureType::Shipyard,
                 });
+                lines.push(line);
             }
-            structures
+            (structures, lines)
         };
         
         let (map_size, resource_map) = {
             let size: MapSize = {
-                read_line_and_tokenize!(buf, tokens, logger);
-                
-                (tokens[0].parse().unwrap(),
-                 tokens[1].parse().unwrap())
+                read_line_and_tokenize!(line, reader, line_number, logger);
+                line.expect_tokens(2)?;
+                (line.integer(0)?, line.integer(1)?)
             };
             
+            // one line per row, i.e. `height` lines of `width` cells; the rows are only kept as
+            // they arrive, so a size the engine does not back with rows allocates nothing
             let cells = {
-                let mut cells = Vec::<Vec<u32>>::new();
-                for row in 0..(size.0 as usize) {
-                    let mut this_row = Vec::<u32>::new();
-                    read_line_and_tokenize!(buf, tokens, logger);
-                    for col in 0..size.1 as usize {
-                        this_row.push(tokens[col].parse().unwrap())
-                    }
-                    cells.push(this_row)
+                let mut rows = Vec::new();
+                for _ in 0..size.1 {
+                    read_line_and_tokenize!(line, reader, line_number, logger);
+                    line.expect_tokens(size.0 as usize)?;
+                    rows.push((0..line.tokens.len()).map(|x| line.integer(x)).collect::<Result<Vec<_>, _>>()?);
                 }
-                cells
+                // with no rows or empty ones the map has no cells at all
+                Grid::from_rows(rows).unwrap_or_else(|| Grid::new(size, 0))
             };
             (size, cells)
         };
         
+        for (structure, line) in structures.iter().zip(shipyard_lines.iter()) {
+            check_position(line, structure.position.x as u32, structure.position.y as u32, map_size)?;
+        }
+        
         let ships = {
             Vec::<Ship>::new()
         };
         
-        let turn_number = 0 as u32;
+        let turn_number = 0;
         
-        let player_owned_halite = {
-            let mut vec = Vec::<HaliteAmount>::new();
-            for player_id in 0..num_players {
-                vec.push(
-                    game_constants
-                        .get("INITIAL_ENERGY")
-                        .unwrap()
-                        .parse()
-                        .unwrap()
-                )
-            }
-            vec
-        };
+        let player_owned_halite = vec![game_constants.initial_energy; num_players as usize];
         
-        Khala {
+        Ok(Khala {
             map_size,
             resource_map,
             game_constants,
             ships,
             structures,
+            ships_by_owner: vec![Vec::new(); num_players as usize],
+            dropoffs_by_owner: vec![Vec::new(); num_players as usize],
             num_players,
             my_id,
             logger,
+            frames: FrameReader::new(line_number),
+            cells: Vec::new(),
             turn_number,
             player_owned_halite,
+            events: TurnEvents::default(),
+            reader,
+            writer,
+        })
+    }
+    
+    fn rebuild_owner_indexes(&mut self) {
+        let num_players = self.num_players as usize;
+        let mut ships_by_owner = vec![Vec::new(); num_players];
+        let mut dropoffs_by_owner = vec![Vec::new(); num_players];
+        for (index, ship) in self.ships.iter().enumerate() {
+            if let Some(owned) = ships_by_owner.get_mut(ship.owner_id as usize) {
+                owned.push(index);
+            }
+        }
+        for (index, structure) in self.structures.iter().enumerate() {
+            if structure.structure_type != StructureType::Dropoff {
+                continue;
+            }
+            if let Some(owned) = dropoffs_by_owner.get_mut(structure.owner_id as usize) {
+                owned.push(index);
+            }
         }
+        self.ships_by_owner = ships_by_owner;
+        self.dropoffs_by_owner = dropoffs_by_owner;
     }
     
     pub fn ready<S: Into<String>>(&mut self, bot_name: S) {
-        self.write_to_stdout(bot_name);
-        self.write_to_stdout("\n");
+        self.write_to_engine(bot_name);
+        self.write_to_engine("\n");
+        self.flush_commands();
     }
     
-    pub fn end_turn(&mut self, commands: &[CommandString]) {
-        commands.iter().for_each(|command| {
-            self.write_to_stdout(command.clone());
-            self.write_to_stdout(" ");
+    /// Sends the turn's commands; this is the only place they are turned into text.
+    pub fn end_turn(&mut self, commands: &TurnCommands) {
+        commands.commands().iter().for_each(|command| {
+            self.write_to_engine(command.to_string());
+            self.write_to_engine(" ");
         });
-        self.write_to_stdout("\n");
+        self.write_to_engine("\n");
+        self.flush_commands();
+    }
+    
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+    
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    
+    /// Sends what was written and puts the log of the turn on disk.
+    fn flush_commands(&mut self) {
+        if let Err(error) = self.writer.flush() {
+            self.logger.error(format!("failed to flush commands to engine: {}", error));
+        }
+        self.logger.flush();
     }
     
-    pub fn write_to_stdout<S: Into<String>>(&mut self, string: S) {
+    pub fn write_to_engine<S: Into<String>>(&mut self, string: S) {
         let string: String = string.into();
-        match stdout().write_all(string.clone().as_bytes()) {
-            Err(io::Error { .. }) => {}
+        match self.writer.write_all(string.as_bytes()) {
+            Err(error) => self.logger.error(format!("failed to write {:?} to engine: {}", string, error)),
             Ok(()) => {
-                self.logger.log(
-                    format!(
-                        "\
-                        [Information] Writing the following String into stdout: \n  \
-                        plain text: \n    \
-                        \"{}\"\n  \
-                        debug: \n    \
-                        {:#?}\
-                        \n", string, string));
+                if self.logger.enabled(Level::Trace) {
+                    self.logger.trace(format!("wrote {:?}", string));
+                }
             }
         }
     }
+    
+    /// The bot's own log; everything in it reaches the disk once per turn, in `end_turn`.
+    pub fn logger_mut(&mut self) -> &mut Logger {
+        &mut self.logger
+    }
 }
 
-impl Update for Khala {
-    fn update(&mut self) {
+impl<R: BufRead, W: Write> Update for Khala<R, W> {
+    type Error = ProtocolError;
+    
+    fn update(&mut self) -> Result<(), ProtocolError> {
         let logger = &mut self.logger;
-        self.turn_number = {
-            read_line_and_tokenize!(buf, tokens, logger);
-            tokens[0].parse().unwrap()
-        };
-        logger.log(format!("=============== TURN {} ================", self.turn_number));
-        for player_id in 0..self.num_players {
-            read_line_and_tokenize!(buf, tokens, logger);
-            let current_player_id: PlayerId = tokens[0].parse().unwrap();
-            let num_ships: u32 = tokens[1].parse().unwrap();
-            let num_dropoffs: u32 = tokens[2].parse().unwrap();
-            let halite: HaliteAmount = tokens[3].parse().unwrap();
-            
-            self.player_owned_halite[current_player_id as usize] = halite;
-            
-            self.ships = {
-                let mut new_ships = Vec::<Ship>::new();
-                for _ in 0..num_ships {
-                    let ship = {
-                        read_line_and_tokenize!(buf, tokens, logger);
-                        let ship_id: ShipId = tokens[0].parse().unwrap();
-                        let position: Position = {
-                            (tokens[1].parse().unwrap(),
-                             tokens[2].parse().unwrap())
-                        };
-                        let cargo = tokens[3].parse().unwrap();
-                        Ship {
-                            owner_id: current_player_id,
-                            ship_id,
-                            cargo,
-                            position,
-                        }
-                    };
-                    
-                    new_ships.push(ship);
+        let frames = &mut self.frames;
+        let reader = &mut self.reader;
+        // `read_frame_line!(N)` reads the next line of exactly N integers
+        macro_rules! read_frame_line {
+            ($count: expr) => {{
+                let values = frames.read::<_, $count>(reader)?;
+                if logger.enabled(Level::Trace) {
+                    logger.trace(format!("line {}: {:?}", frames.line_number(), frames.raw()));
                 }
-                new_ships
-            };
+                values
+            }};
+        }
+        
+        // everything is read into locals first, so a frame that fails halfway changes nothing
+        let [turn_number] = read_frame_line!(1);
+        logger.set_turn(turn_number);
+        if logger.enabled(Level::Debug) {
+            logger.debug(format!("=============== TURN {} ================", turn_number));
+        }
+        let mut player_owned_halite = self.player_owned_halite.clone();
+        let mut ships = Vec::<Ship>::with_capacity(self.ships.len());
+        let mut structures: Vec<Structure> = self.structures
+            .iter()
+            .filter(|structure| structure.structure_type == StructureType::Shipyard)
+            .cloned()
+            .collect();
+        
+        for _ in 0..self.num_players {
+            let [player_id, num_ships, num_dropoffs, halite] = read_frame_line!(4);
+            let current_player_id: PlayerId = check_player(&*frames, player_id, self.num_players)?;
+            player_owned_halite[current_player_id as usize] = halite;
             
-            self.structures = {
-                let mut new_structures: Vec<Structure> = {
-                    let (shipyards, dropoffs) = self.structures.iter().partition(|structure| {
-                        structure.structure_type == StructureType::Shipyard
-                    });
-                    shipyards
-                };
-                
-                for _ in 0..num_dropoffs {
-                    let structure = {
-                        read_line_and_tokenize!(buf, tokens, logger);
-                        let structure_id: ShipId = tokens[0].parse().unwrap();
-                        let position: Position = {
-                            (tokens[1].parse().unwrap(),
-                             tokens[2].parse().unwrap())
-                        };
-                        let structure_type = StructureType::Dropoff;
-                        Structure {
-                            owner_id: current_player_id,
-                            structure_id,
-                            position,
-                            structure_type,
-                        }
-                    };
-                    
-                    new_structures.push(structure);
-                }
-                new_structures
-            };
+            for _ in 0..num_ships {
+                let [ship_id, x, y, cargo] = read_frame_line!(4);
+                ships.push(Ship {
+                    owner_id: current_player_id,
+                    ship_id,
+                    cargo,
+                    position: check_position(&*frames, x, y, self.map_size)?,
+                });
+            }
+            
+            for _ in 0..num_dropoffs {
+                let [structure_id, x, y] = read_frame_line!(3);
+                structures.push(Structure {
+                    owner_id: current_player_id,
+                    structure_id,
+                    position: check_position(&*frames, x, y, self.map_size)?,
+                    structure_type: StructureType::Dropoff,
+                });
+            }
         }
-        logger.log(format!("Done updating players and entities"));
-        let update_count = {
-            read_line_and_tokenize!(buf, tokens, logger);
-            tokens[0].parse().unwrap()
-        };
-        logger.log(format!("Resource map needs update, update_count: {}", update_count));
+        
+        let [update_count] = read_frame_line!(1);
+        if logger.enabled(Level::Trace) {
+            logger.trace(format!("update_count: {}", update_count));
+        }
+        // grows only with the lines actually read, whatever count the engine claims
+        let cells = &mut self.cells;
+        cells.clear();
         for _ in 0..update_count {
-            read_line_and_tokenize!(buf, tokens, logger);
-            let (x, y): MapSize = {
-                (tokens[0].parse().unwrap(),
-                 tokens[1].parse().unwrap())
-            };
-            let halite: HaliteAmount = tokens[2].parse().unwrap();
-            
-            self.resource_map[y as usize][x as usize] = halite;
+            let [x, y, halite] = read_frame_line!(3);
+            let position = check_position(&*frames, x, y, self.map_size)?;
+            cells.push((position, self.resource_map[position], halite));
+        }
+        
+        self.turn_number = turn_number;
+        let old_halite = std::mem::replace(&mut self.player_owned_halite, player_owned_halite);
+        let old_ships = std::mem::replace(&mut self.ships, ships);
+        let old_structures = std::mem::replace(&mut self.structures, structures);
+        for &(position, _, halite) in self.cells.iter() {
+            self.resource_map[position] = halite;
         }
+        self.rebuild_owner_indexes();
+        let before = Frame {
+            ships: &old_ships,
+            structures: &old_structures,
+            player_owned_halite: &old_halite,
+        };
+        let after = Frame {
+            ships: &self.ships,
+            structures: &self.structures,
+            player_owned_halite: &self.player_owned_halite,
+        };
+        self.events = events::diff(self.turn_number, &self.game_constants, self.map_size, before, after, &self.cells);
+        Ok(())
     }
 }
 
@@ -361,46 +414,174 @@ pub enum StructureType {
     Dropoff,
 }
 
-use super::position::{RecursiveCellPosition, SizedGrid2D};
+use super::position::SizedGrid2D;
 
 pub trait Grid2D<T> {
     fn get_at_position(&self, position: Position) -> T;
 }
 
-impl Grid2D<HaliteAmount> for Khala {
+impl<R, W> Grid2D<HaliteAmount> for Khala<R, W> {
     fn get_at_position(&self, position: Position) -> HaliteAmount {
-        self.resource_map[position.1 as usize][position.0 as usize]
+        self.resource_map.get_at_position(position)
     }
 }
 
-impl SizedGrid2D for Khala {
+/// Goes into the bot's own log at `Info`.
+impl<R, W> Log for Khala<R, W> {
+    fn write_line(&mut self, line: &str) {
+        self.logger.info(line);
+    }
+    
+    fn write_record(&mut self, record: &Record) {
+        self.logger.log_record(record.clone());
+    }
+    
+    fn flush(&mut self) {
+        self.logger.flush();
+    }
+}
+
+impl<R, W> SizedGrid2D for Khala<R, W> {
     fn get_size(&self) -> (u32, u32) {
         self.map_size
     }
 }
 
-pub trait Command<S> {
-    fn spawn_ship() -> S;
-    fn transform_ship_into_dropoff_site(ship_id: ShipId) -> S;
-    fn move_ship_by_direction<D: FourDirection + fmt::Display>(ship_id: ShipId, direction: D) -> S;
-    fn hold_ship(ship_id: ShipId) -> S;
+/// One player with no ships on a 1x1 map, for two turns.
+#[cfg(test)]
+pub(crate) const TWO_TURNS: &str = "{}\n1 0\n0 0 0\n1 1\n0\n1\n0 0 0 5000\n0\n2\n0 0 0 4000\n0\n";
+
+/// A `Khala` reading the engine's side from `input`, with logging off so that tests leave no
+/// files behind.
+#[cfg(test)]
+pub(crate) fn khala_from(input: &str) -> Khala<std::io::Cursor<Vec<u8>>, Vec<u8>> {
+    let reader = std::io::Cursor::new(input.as_bytes().to_vec());
+    Khala::read_with_log(reader, Vec::new(), &LogConfig::disabled()).unwrap()
 }
 
-impl Command<CommandString> for Khala {
-    fn spawn_ship() -> CommandString {
-        format!("g")
+
+#[cfg(test)]
+mod tests {
+    use super::{khala_from as khala, Grid2D, Khala, StructureType};
+    use crate::halite::command::TurnCommands;
+    use crate::halite::direction::Direction;
+    use crate::halite::error::ProtocolError;
+    use crate::halite::log::LogConfig;
+    use crate::halite::position::Position;
+    use crate::halite::update::Update;
+    use std::io::Cursor;
+    
+    const INIT: &str = "\
+{\"INITIAL_ENERGY\":5000,\"MAX_TURNS\":400}
+2 1
+0 1 1
+1 2 2
+4 4
+10 20 30 40
+11 21 31 41
+12 22 32 42
+13 23 33 43
+";
+    
+    const TURN_1: &str = "\
+1
+0 2 1 4000
+0 1 1 0
+1 0 1 50
+7 3 3
+1 1 0 3000
+2 2 3 900
+2
+0 0 0
+3 3 7
+";
+    
+    #[test]
+    fn reads_initial_state() {
+        let khala = khala(INIT);
+        assert_eq!(khala.num_players, 2);
+        assert_eq!(khala.my_id, 1);
+        assert_eq!(khala.player_owned_halite, vec![5000, 5000]);
+        assert_eq!(khala.resource_map[Position::new(2, 1)], 31);
+        assert_eq!(khala.shipyard_of(1).unwrap().position, Position::new(2, 2));
+        assert_eq!(khala.game_constants.max_turns, 400);
+    }
+    
+    #[test]
+    fn keeps_entities_of_every_player() {
+        let mut khala = khala(&format!("{}{}", INIT, TURN_1));
+        khala.update().unwrap();
+        
+        assert_eq!(khala.turn_number, 1);
+        assert_eq!(khala.ships.len(), 3);
+        assert_eq!(khala.ships_of(0).map(|ship| ship.ship_id).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(khala.ships_of(1).map(|ship| ship.ship_id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(khala.dropoffs_of(0).map(|dropoff| dropoff.position).collect::<Vec<_>>(),
+                   vec![Position::new(3, 3)]);
+        assert_eq!(khala.dropoffs_of(1).count(), 0);
+        assert_eq!(khala.structures_of(0).count(), 2);
+        assert_eq!(khala.structures.iter()
+                        .filter(|structure| structure.structure_type == StructureType::Shipyard)
+                        .count(), 2);
+        assert_eq!(khala.player_owned_halite, vec![4000, 3000]);
+        assert_eq!(khala.resource_map[Position::new(0, 0)], 0);
+        assert_eq!(khala.resource_map[Position::new(3, 3)], 7);
+        
+        match khala.update() {
+            Err(ProtocolError::UnexpectedEof { .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
     }
     
-    fn transform_ship_into_dropoff_site(ship_id: ShipId) -> CommandString {
-        format!("c {}", ship_id)
+    #[test]
+    fn reads_height_rows_of_width_cells() {
+        let khala = khala("{}\n1 0\n0 2 0\n3 2\n1 2 3\n4 5 6\n");
+        assert_eq!(khala.resource_map.size(), (3, 2));
+        assert_eq!(khala.resource_map[Position::new(2, 0)], 3);
+        assert_eq!(khala.resource_map[Position::new(0, 1)], 4);
+        assert_eq!(khala.get_at_position(Position::new(3, 1)), 4);
     }
     
-    fn move_ship_by_direction<D: FourDirection + fmt::Display>(ship_id: ShipId, direction: D) ->
-    CommandString {
-        format!("m {} {}", ship_id, direction)
+    #[test]
+    fn rejects_an_update_count_without_cells_instead_of_allocating_it() {
+        let mut khala = khala(&format!("{}1\n0 0 0 5000\n1 0 0 5000\n4000000000\n0 0 1\n", INIT));
+        match khala.update() {
+            Err(ProtocolError::UnexpectedEof { .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(khala.turn_number, 0);
+    }
+    
+    #[test]
+    fn rejects_a_map_size_without_rows_instead_of_allocating_it() {
+        let reader = Cursor::new(b"{}\n1 0\n0 0 0\n4000000000 4000000000\n1 2\n".to_vec());
+        match Khala::read_with_log(reader, Vec::new(), &LogConfig::disabled()) {
+            Err(ProtocolError::TokenCount { found: 2, .. }) => {}
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+    
+    #[test]
+    fn rejects_positions_outside_of_the_map() {
+        let mut khala = khala(&format!("{}1\n0 1 0 0\n0 4 0 0\n", INIT));
+        match khala.update() {
+            Err(ProtocolError::OutOfBounds { line: 12, position, .. }) if position == Position::new(4, 0) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        // the half-read frame is not applied
+        assert_eq!((khala.turn_number, khala.player_owned_halite.clone()), (0, vec![5000, 5000]));
+        assert!(khala.ships.is_empty());
     }
     
-    fn hold_ship(ship_id: ShipId) -> CommandString {
-        format!("m {} {}", ship_id, &"o")
+    #[test]
+    fn writes_commands_into_the_writer() {
+        let mut khala = khala(&format!("{}{}", INIT, TURN_1));
+        khala.ready("bot");
+        khala.update().unwrap();
+        let mut commands = TurnCommands::new(&khala);
+        commands.spawn().unwrap();
+        commands.move_ship(2, Direction::Still).unwrap();
+        khala.end_turn(&commands);
+        assert_eq!(String::from_utf8(khala.writer().clone()).unwrap(), "bot\ng m 2 o \n");
     }
 }
@@ -0,0 +1,185 @@
+//! A value per map cell, stored row after row. Positions wrap around the edges the way ships do,
+//! so `(width, 0)` and `(-width, 0)` are the same cell as `(0, 0)`.
+
+use std::ops::{Index, IndexMut};
+use std::slice;
+use std::sync::Arc;
+use super::game::{Grid2D, MapSize};
+use super::position::{Position, RecursiveCellPosition, SizedGrid2D};
+
+/// Cloning only copies a pointer; the cells are copied the first time either clone is written
+/// to, so a bot can keep last turn's layer around for free.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    size: MapSize,
+    cells: Arc<Vec<T>>,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid of `size` with the value `f` returns for every position.
+    pub fn from_fn<F: FnMut(Position) -> T>(size: MapSize, f: F) -> Grid<T> {
+        let (width, height) = size;
+        let cells = (0..height as i32).flat_map(|y| (0..width as i32).map(move |x| Position::new(x, y)))
+                               .map(f)
+                               .collect();
+        Grid {
+            size,
+            cells: Arc::new(cells),
+        }
+    }
+    
+    /// `None` unless every row has the same, non zero, length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Grid<T>> {
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        if width == 0 || rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        let size = (width as u32, rows.len() as u32);
+        Some(Grid {
+            size,
+            cells: Arc::new(rows.into_iter().flatten().collect()),
+        })
+    }
+    
+    pub fn size(&self) -> MapSize {
+        self.size
+    }
+    
+    pub fn width(&self) -> u32 {
+        self.size.0
+    }
+    
+    pub fn height(&self) -> u32 {
+        self.size.1
+    }
+    
+    /// The position inside the map that `position` wraps around to.
+    pub fn wrap(&self, position: Position) -> Position {
+        position.normalize(&self.size)
+    }
+    
+    fn index_of(&self, position: Position) -> usize {
+        let position = self.wrap(position);
+        position.y as usize * self.size.0 as usize + position.x as usize
+    }
+    
+    pub fn get(&self, position: Position) -> &T {
+        &self.cells[self.index_of(position)]
+    }
+    
+    /// Every cell with its position, row after row.
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        let width = self.size.0 as usize;
+        self.cells.iter().enumerate().map(move |(index, value)| {
+            (Position::new((index % width) as i32, (index / width) as i32), value)
+        })
+    }
+    
+    pub fn values(&self) -> slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+    
+    pub fn rows(&self) -> slice::Chunks<'_, T> {
+        self.cells.chunks(self.size.0 as usize)
+    }
+    
+    /// A new layer of the same size, e.g. the move cost of every cell out of the halite layer.
+    pub fn map<U, F: FnMut(Position, &T) -> U>(&self, mut f: F) -> Grid<U> {
+        Grid {
+            size: self.size,
+            cells: Arc::new(self.iter().map(|(position, value)| f(position, value)).collect()),
+        }
+    }
+    
+    /// Combines two layers of the same size cell by cell.
+    pub fn zip<U, V, F: FnMut(&T, &U) -> V>(&self, other: &Grid<U>, mut f: F) -> Grid<V> {
+        assert_eq!(self.size, other.size, "only grids of the same size can be zipped");
+        Grid {
+            size: self.size,
+            cells: Arc::new(self.values().zip(other.values()).map(|(a, b)| f(a, b)).collect()),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(size: MapSize, value: T) -> Grid<T> {
+        let cells = (size.0 as usize).checked_mul(size.1 as usize).expect("the map has too many cells");
+        Grid {
+            size,
+            cells: Arc::new(vec![value; cells]),
+        }
+    }
+    
+    /// Copies the cells first if another clone still shares them.
+    pub fn get_mut(&mut self, position: Position) -> &mut T {
+        let index = self.index_of(position);
+        &mut Arc::make_mut(&mut self.cells)[index]
+    }
+    
+    pub fn set(&mut self, position: Position, value: T) {
+        *self.get_mut(position) = value;
+    }
+    
+    pub fn values_mut(&mut self) -> slice::IterMut<'_, T> {
+        Arc::make_mut(&mut self.cells).iter_mut()
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+    
+    fn index(&self, position: Position) -> &T {
+        self.get(position)
+    }
+}
+
+impl<T: Clone> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        self.get_mut(position)
+    }
+}
+
+impl<T: Copy> Grid2D<T> for Grid<T> {
+    fn get_at_position(&self, position: Position) -> T {
+        *self.get(position)
+    }
+}
+
+impl<T> SizedGrid2D for Grid<T> {
+    fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Grid;
+    use crate::halite::position::Position;
+    
+    #[test]
+    fn indexes_row_after_row_and_wraps() {
+        let grid = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+        assert_eq!(grid.size(), (3, 2));
+        assert_eq!(grid[Position::new(2, 0)], 3);
+        assert_eq!(grid[Position::new(0, 1)], 4);
+        assert_eq!(grid[Position::new(3, 1)], 4);
+        assert_eq!(grid[Position::new(1, -2)], 2);
+        assert_eq!(grid.iter().nth(4), Some((Position::new(1, 1), &5)));
+        assert!(Grid::from_rows(vec![vec![1], vec![2, 3]]).is_none());
+    }
+    
+    #[test]
+    fn clones_share_cells_until_written() {
+        let halite = Grid::from_fn((4, 3), |position| position.x + 10 * position.y);
+        let mut mined = halite.clone();
+        mined[Position::new(1, 2)] = 0;
+        assert_eq!(halite[Position::new(1, 2)], 21);
+        assert_eq!(mined[Position::new(1, 2)], 0);
+        
+        let cost = halite.map(|_, &halite| halite / 10);
+        let total = halite.zip(&cost, |&halite, &cost| halite + cost);
+        assert_eq!(total[Position::new(3, 2)], 23 + 2);
+        assert_eq!(total.rows().count(), 3);
+    }
+}
@@ -1,13 +1,243 @@
-pub fn tokenize(string: &mut String) -> Vec<String> {
-    let tokens: Vec<String> = Vec::new();
+use std::io::BufRead;
+use std::str;
+use super::error::ProtocolError;
+
+/// One line received from the engine, kept around so errors can point at it.
+#[derive(Clone, Debug)]
+pub struct Line {
+    pub number: usize,
+    pub raw: String,
+    pub tokens: Vec<String>,
+}
+
+impl Line {
+    /// Reads and tokenizes the next line, bumping `line_number`.
+    pub fn read<R: BufRead>(reader: &mut R, line_number: &mut usize) -> Result<Line, ProtocolError> {
+        *line_number += 1;
+        let mut buf = String::new();
+        match reader.read_line(&mut buf) {
+            Ok(0) => Err(ProtocolError::UnexpectedEof { line: *line_number }),
+            Ok(_) => {
+                let tokens = buf.split_whitespace().map(String::from).collect();
+                let raw = buf.trim_end_matches(['\r', '\n']).to_string();
+                Ok(Line {
+                    number: *line_number,
+                    raw,
+                    tokens,
+                })
+            }
+            Err(source) => Err(ProtocolError::Io { line: *line_number, source }),
+        }
+    }
+    
+    pub fn expect_tokens(&self, expected: usize) -> Result<&Line, ProtocolError> {
+        if self.tokens.len() == expected {
+            Ok(self)
+        } else {
+            Err(ProtocolError::TokenCount {
+                line: self.number,
+                raw: self.raw.clone(),
+                expected,
+                found: self.tokens.len(),
+            })
+        }
+    }
+    
+    pub fn integer(&self, index: usize) -> Result<u32, ProtocolError> {
+        let token = match self.tokens.get(index) {
+            Some(token) => token,
+            None => {
+                return Err(ProtocolError::TokenCount {
+                    line: self.number,
+                    raw: self.raw.clone(),
+                    expected: index + 1,
+                    found: self.tokens.len(),
+                });
+            }
+        };
+        token.parse().map_err(|_| ProtocolError::BadInteger {
+            line: self.number,
+            raw: self.raw.clone(),
+            token: token.clone(),
+        })
+    }
+}
+
+
+/// Where a value came from, for the errors about it.
+pub trait LineContext {
+    fn line_number(&self) -> usize;
+    fn raw_line(&self) -> String;
+}
+
+impl LineContext for Line {
+    fn line_number(&self) -> usize {
+        self.number
+    }
+    
+    fn raw_line(&self) -> String {
+        self.raw.clone()
+    }
+}
+
+/// Reads the lines of frame updates into one buffer that is kept from line to line and parses
+/// their integers straight from its bytes, so a turn is read without allocating. Only errors
+/// copy the line.
+#[derive(Clone, Debug, Default)]
+pub struct FrameReader {
+    buf: Vec<u8>,
+    line_number: usize,
+}
+
+impl FrameReader {
+    /// Continues after the `line_number` lines read so far.
+    pub fn new(line_number: usize) -> FrameReader {
+        FrameReader {
+            buf: Vec::with_capacity(64),
+            line_number,
+        }
+    }
+    
+    /// The line last read, without its line break.
+    pub fn raw(&self) -> &str {
+        let mut end = self.buf.len();
+        while end > 0 && (self.buf[end - 1] == b'\n' || self.buf[end - 1] == b'\r') {
+            end -= 1;
+        }
+        str::from_utf8(&self.buf[..end]).unwrap_or("<not utf-8>")
+    }
     
-    let tokens = string
-        .split_whitespace()
-        .filter(|x| !x.is_empty())
-        .map(|x| x.to_string())
-        .collect();
+    /// Reads the next line, which has to be exactly `N` integers.
+    pub fn read<R: BufRead, const N: usize>(&mut self, reader: &mut R) -> Result<[u32; N], ProtocolError> {
+        self.line_number += 1;
+        self.buf.clear();
+        match reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => return Err(ProtocolError::UnexpectedEof { line: self.line_number }),
+            Ok(_) => {}
+            Err(source) => return Err(ProtocolError::Io { line: self.line_number, source }),
+        }
+        
+        let mut values = [0; N];
+        let mut found = 0;
+        let mut token_start = None;
+        for (index, &byte) in self.buf.iter().enumerate() {
+            if !byte.is_ascii_whitespace() {
+                token_start = token_start.or(Some(index));
+                continue;
+            }
+            if let Some(start) = token_start.take() {
+                self.store(&mut values, found, start, index)?;
+                found += 1;
+            }
+        }
+        if let Some(start) = token_start {
+            self.store(&mut values, found, start, self.buf.len())?;
+            found += 1;
+        }
+        if found != N {
+            return Err(ProtocolError::TokenCount {
+                line: self.line_number,
+                raw: self.raw_line(),
+                expected: N,
+                found,
+            });
+        }
+        Ok(values)
+    }
     
-    tokens
+    /// Parses the token at `buf[start..end]` into `values[index]` if there is room for it; tokens
+    /// past the end are only counted.
+    fn store<const N: usize>(&self, values: &mut [u32; N], index: usize, start: usize, end: usize)
+                             -> Result<(), ProtocolError> {
+        let token = &self.buf[start..end];
+        let mut value: u32 = 0;
+        for &byte in token {
+            let digit = byte.wrapping_sub(b'0');
+            let next = if digit < 10 {
+                value.checked_mul(10).and_then(|value| value.checked_add(digit as u32))
+            } else {
+                None
+            };
+            value = match next {
+                Some(next) => next,
+                None => {
+                    return Err(ProtocolError::BadInteger {
+                        line: self.line_number,
+                        raw: self.raw_line(),
+                        token: String::from_utf8_lossy(token).into_owned(),
+                    });
+                }
+            };
+        }
+        if let Some(slot) = values.get_mut(index) {
+            *slot = value;
+        }
+        Ok(())
+    }
+}
+
+impl LineContext for FrameReader {
+    fn line_number(&self) -> usize {
+        self.line_number
+    }
+    
+    fn raw_line(&self) -> String {
+        self.raw().to_string()
+    }
 }
 
 
+#[cfg(test)]
+mod tests {
+    use super::{FrameReader, Line};
+    use crate::halite::error::ProtocolError;
+    
+    #[test]
+    fn reads_lines_and_counts_them() {
+        let mut input = "2 1\n7 x\n".as_bytes();
+        let mut line_number = 0;
+        
+        let first = Line::read(&mut input, &mut line_number).unwrap();
+        assert_eq!(first.number, 1);
+        assert_eq!(first.expect_tokens(2).unwrap().integer(1).unwrap(), 1);
+        
+        let second = Line::read(&mut input, &mut line_number).unwrap();
+        match second.integer(1) {
+            Err(ProtocolError::BadInteger { line: 2, raw, token }) => {
+                assert_eq!(raw, "7 x");
+                assert_eq!(token, "x");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match second.expect_tokens(3) {
+            Err(ProtocolError::TokenCount { expected: 3, found: 2, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        
+        match Line::read(&mut input, &mut line_number) {
+            Err(ProtocolError::UnexpectedEof { line: 3 }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn parses_integers_in_place() {
+        let mut input = "3 14 159 26\r\n  7   8\n1 -2\n99999999999\n".as_bytes();
+        let mut frames = FrameReader::new(10);
+        assert_eq!(frames.read(&mut input).unwrap(), [3, 14, 159, 26]);
+        assert_eq!(frames.raw(), "3 14 159 26");
+        match frames.read::<_, 3>(&mut input) {
+            Err(ProtocolError::TokenCount { line: 12, expected: 3, found: 2, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match frames.read::<_, 2>(&mut input) {
+            Err(ProtocolError::BadInteger { raw, token, .. }) => assert_eq!((raw.as_str(), token.as_str()), ("1 -2", "-2")),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(frames.read::<_, 1>(&mut input).is_err());
+        match frames.read::<_, 1>(&mut input) {
+            Err(ProtocolError::UnexpectedEof { line: 15 }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
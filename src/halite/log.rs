@@ -1,37 +1,540 @@
+//! Leveled logging into pluggable sinks. Lines are buffered and only reach the disk when the
+//! logger is flushed, which `Khala` does once per turn. Every message is a `Record`; text sinks
+//! write it as a line, `JsonSink` as one JSON object per line for analysis scripts.
+
+use std::cell::RefCell;
+use std::env;
+use std::fmt;
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
-use std::path::Path;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+use serde_json::{json, Value};
+use super::game::ShipId;
+use super::position::Position;
+use super::registry::Mission;
 
+/// Anything lines can be written to.
 pub trait Log {
-    fn log<S: Into<String>>(&mut self, message: S);
+    fn write_line(&mut self, line: &str);
+    
+    /// Sinks that keep the fields override this, the others get the record as a line.
+    fn write_record(&mut self, record: &Record) {
+        self.write_line(&record.to_string());
+    }
+    
+    /// Makes sure everything written so far is out of the buffers.
+    fn flush(&mut self) {}
+    
+    fn log<S: Into<String>>(&mut self, message: S) where Self: Sized {
+        self.write_line(&message.into());
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Level, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(Level::Trace),
+            "debug" => Ok(Level::Debug),
+            "info" => Ok(Level::Info),
+            "warn" | "warning" => Ok(Level::Warn),
+            "error" => Ok(Level::Error),
+            _ => Err(format!("unknown log level `{}`", s)),
+        }
+    }
+}
+
+/// One message with what it is about. Fields that are not set stay out of the text line and are
+/// `null` in JSON.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Record {
+    pub level: Level,
+    /// filled in by the `Logger` when not set
+    pub turn: Option<u32>,
+    pub ship_id: Option<ShipId>,
+    pub position: Option<Position>,
+    pub mission: Option<Mission>,
+    pub message: String,
+}
+
+impl Record {
+    pub fn new<S: Into<String>>(level: Level, message: S) -> Record {
+        Record {
+            level,
+            turn: None,
+            ship_id: None,
+            position: None,
+            mission: None,
+            message: message.into(),
+        }
+    }
+    
+    pub fn turn(mut self, turn: u32) -> Record {
+        self.turn = Some(turn);
+        self
+    }
+    
+    pub fn ship(mut self, ship_id: ShipId) -> Record {
+        self.ship_id = Some(ship_id);
+        self
+    }
+    
+    pub fn position(mut self, position: Position) -> Record {
+        self.position = Some(position);
+        self
+    }
+    
+    pub fn mission(mut self, mission: Mission) -> Record {
+        self.mission = Some(mission);
+        self
+    }
+    
+    pub fn to_json(&self) -> Value {
+        json!({
+            "level": self.level.name(),
+            "turn": self.turn,
+            "ship_id": self.ship_id,
+            "position": self.position.map(|position| json!({ "x": position.x, "y": position.y })),
+            "mission": self.mission.map(|mission| format!("{:?}", mission)),
+            "message": self.message,
+        })
+    }
+}
+
+/// `[info] ship 3 at 4 5 Mining: message`, the turn is left to the turn headers.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] ", self.level)?;
+        if let Some(ship_id) = self.ship_id {
+            write!(f, "ship {} ", ship_id)?;
+        }
+        if let Some(position) = self.position {
+            write!(f, "at {} ", position)?;
+        }
+        if let Some(mission) = self.mission {
+            write!(f, "{:?}: ", mission)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Appends to a file through a buffer.
+#[derive(Debug)]
+pub struct FileSink {
+    writer: BufWriter<File>,
+}
+
+impl FileSink {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<FileSink> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileSink { writer: BufWriter::new(file) })
+    }
+}
+
+impl Log for FileSink {
+    fn write_line(&mut self, line: &str) {
+        // a full disk is no reason to lose the game
+        let _ = writeln!(self.writer, "{}", line);
+    }
+    
+    fn flush(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Writes to stderr, which the engine keeps in its own log of the bot.
+#[derive(Clone, Debug, Default)]
+pub struct StderrSink;
+
+impl Log for StderrSink {
+    fn write_line(&mut self, line: &str) {
+        let _ = writeln!(io::stderr().lock(), "{}", line);
+    }
+}
+
+/// Keeps the records in memory, for tests. Clones share them, so one clone can be handed to a
+/// `Logger` and the other read from.
+#[derive(Clone, Debug, Default)]
+pub struct MemorySink {
+    records: Rc<RefCell<Vec<Record>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+    
+    pub fn records(&self) -> Vec<Record> {
+        self.records.borrow().clone()
+    }
+    
+    /// The records as text sinks write them.
+    pub fn lines(&self) -> Vec<String> {
+        self.records.borrow().iter().map(Record::to_string).collect()
+    }
+}
+
+impl Log for MemorySink {
+    fn write_line(&mut self, line: &str) {
+        self.write_record(&Record::new(Level::Info, line));
+    }
+    
+    fn write_record(&mut self, record: &Record) {
+        self.records.borrow_mut().push(record.clone());
+    }
 }
 
+/// One JSON object per record and line.
 #[derive(Debug)]
+pub struct JsonSink<W: Write = BufWriter<File>> {
+    writer: W,
+}
+
+impl JsonSink {
+    /// Appends to the file at `path` through a buffer.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<JsonSink> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonSink::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn new(writer: W) -> JsonSink<W> {
+        JsonSink { writer }
+    }
+}
+
+impl<W: Write> Log for JsonSink<W> {
+    /// A line on its own becomes the message of an `Info` record.
+    fn write_line(&mut self, line: &str) {
+        self.write_record(&Record::new(Level::Info, line));
+    }
+    
+    fn write_record(&mut self, record: &Record) {
+        let _ = writeln!(self.writer, "{}", record.to_json());
+    }
+    
+    fn flush(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Drops messages below its level and prefixes the others with their level before handing them
+/// to every sink. Without sinks nothing is logged at all.
 pub struct Logger {
-    file: File,
+    level: Level,
+    turn: Option<u32>,
+    sinks: Vec<Box<dyn Log>>,
 }
 
 impl Logger {
-    pub fn new<S1: Into<String>, S2: Into<String>>(dir: S1, filename: S2) -> Logger
-        where S1: std::convert::AsRef<std::ffi::OsStr> {
-        let dir_path = Path::new(&dir);
-        let file_path = dir_path.join(filename.into());
-        let file = {
-            if !file_path.exists() {
-                File::create(file_path).unwrap()
-            } else {
-                OpenOptions::new().append(true).open(file_path).unwrap()
-            }
-        };
-        Logger {
-            file,
+    pub fn new(level: Level) -> Logger {
+        Logger { level, turn: None, sinks: Vec::new() }
+    }
+    
+    pub fn with_sink<L: Log + 'static>(mut self, sink: L) -> Logger {
+        self.add_sink(sink);
+        self
+    }
+    
+    pub fn add_sink<L: Log + 'static>(&mut self, sink: L) {
+        self.sinks.push(Box::new(sink));
+    }
+    
+    pub fn level(&self) -> Level {
+        self.level
+    }
+    
+    pub fn set_level(&mut self, level: Level) {
+        self.level = level;
+    }
+    
+    /// The turn records are about unless they say otherwise.
+    pub fn set_turn(&mut self, turn: u32) {
+        self.turn = Some(turn);
+    }
+    
+    /// Whether a message at `level` would go anywhere, to skip building expensive ones.
+    pub fn enabled(&self, level: Level) -> bool {
+        level >= self.level && !self.sinks.is_empty()
+    }
+    
+    pub fn log_at<S: Into<String>>(&mut self, level: Level, message: S) {
+        if self.enabled(level) {
+            self.log_record(Record::new(level, message));
+        }
+    }
+    
+    pub fn log_record(&mut self, mut record: Record) {
+        if !self.enabled(record.level) {
+            return;
+        }
+        if record.turn.is_none() {
+            record.turn = self.turn;
         }
+        for sink in self.sinks.iter_mut() {
+            sink.write_record(&record);
+        }
+    }
+    
+    pub fn trace<S: Into<String>>(&mut self, message: S) {
+        self.log_at(Level::Trace, message);
+    }
+    
+    pub fn debug<S: Into<String>>(&mut self, message: S) {
+        self.log_at(Level::Debug, message);
+    }
+    
+    pub fn info<S: Into<String>>(&mut self, message: S) {
+        self.log_at(Level::Info, message);
+    }
+    
+    pub fn warn<S: Into<String>>(&mut self, message: S) {
+        self.log_at(Level::Warn, message);
+    }
+    
+    pub fn error<S: Into<String>>(&mut self, message: S) {
+        self.log_at(Level::Error, message);
     }
 }
 
+/// Plain lines are logged at `Info`.
 impl Log for Logger {
-    fn log<S: Into<String>>(&mut self, message: S) {
-        writeln!(self.file, "{}", message.into()).unwrap();
-        self.file.sync_all();
+    fn write_line(&mut self, line: &str) {
+        self.info(line);
+    }
+    
+    fn write_record(&mut self, record: &Record) {
+        self.log_record(record.clone());
+    }
+    
+    fn flush(&mut self) {
+        for sink in self.sinks.iter_mut() {
+            sink.flush();
+        }
+    }
+}
+
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Logger")
+         .field("level", &self.level)
+         .field("turn", &self.turn)
+         .field("sinks", &self.sinks.len())
+         .finish()
+    }
+}
+
+/// How the log file is written.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LogFormat {
+    Text,
+    /// JSON lines, into a `.jsonl` file
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<LogFormat, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" | "jsonl" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format `{}`", s)),
+        }
+    }
+}
+
+/// Where a bot logs to and how, from `HALITE_LOG_LEVEL`, `HALITE_LOG_DIR`, `HALITE_LOG_FORMAT`
+/// and `HALITE_LOG_STDERR` or the matching command line options.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LogConfig {
+    pub level: Level,
+    /// the directory of the log file, no file when `None`
+    pub dir: Option<PathBuf>,
+    pub format: LogFormat,
+    pub stderr: bool,
+}
+
+impl Default for LogConfig {
+    /// `Info` and above into the working directory.
+    fn default() -> LogConfig {
+        LogConfig {
+            level: Level::Info,
+            dir: Some(PathBuf::from(".")),
+            format: LogFormat::Text,
+            stderr: false,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Logs nothing.
+    pub fn disabled() -> LogConfig {
+        LogConfig { level: Level::Error, dir: None, format: LogFormat::Text, stderr: false }
+    }
+    
+    /// The defaults overridden by the environment; an empty `HALITE_LOG_DIR` turns the file off.
+    pub fn from_env() -> LogConfig {
+        let mut config = LogConfig::default();
+        if let Some(level) = env::var("HALITE_LOG_LEVEL").ok().and_then(|level| level.parse().ok()) {
+            config.level = level;
+        }
+        if let Ok(dir) = env::var("HALITE_LOG_DIR") {
+            config.dir = if dir.is_empty() { None } else { Some(PathBuf::from(dir)) };
+        }
+        if let Some(format) = env::var("HALITE_LOG_FORMAT").ok().and_then(|format| format.parse().ok()) {
+            config.format = format;
+        }
+        if let Ok(stderr) = env::var("HALITE_LOG_STDERR") {
+            config.stderr = stderr == "1" || stderr == "true";
+        }
+        config
+    }
+    
+    /// Overrides with `--log-level <level>`, `--log-dir <dir>`, `--log-format <text|json>` and
+    /// `--log-stderr` from `args`, which come without the program name. Returns the arguments
+    /// that are not about logging, or what is wrong with a level or format that does not parse.
+    pub fn with_args<I: IntoIterator<Item = String>>(mut self, args: I) -> Result<(LogConfig, Vec<String>), String> {
+        let mut rest = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--log-level" => {
+                    self.level = args.next().ok_or("--log-level needs a level")?.parse()?;
+                }
+                "--log-dir" => {
+                    self.dir = args.next().filter(|dir| !dir.is_empty()).map(PathBuf::from);
+                }
+                "--log-format" => {
+                    self.format = args.next().ok_or("--log-format needs a format")?.parse()?;
+                }
+                "--log-stderr" => self.stderr = true,
+                _ => rest.push(arg),
+            }
+        }
+        Ok((self, rest))
+    }
+    
+    /// A logger writing into `filename` in the log directory, with a `.jsonl` extension for
+    /// JSON. When the file cannot be opened the logger says so on stderr and carries on without it.
+    pub fn logger(&self, filename: &str) -> Logger {
+        let mut logger = Logger::new(self.level);
+        if self.stderr {
+            logger.add_sink(StderrSink);
+        }
+        if let Some(dir) = &self.dir {
+            let path = match self.format {
+                LogFormat::Text => dir.join(filename),
+                LogFormat::Json => dir.join(filename).with_extension("jsonl"),
+            };
+            let opened = match self.format {
+                LogFormat::Text => FileSink::open(&path).map(|sink| logger.add_sink(sink)),
+                LogFormat::Json => JsonSink::open(&path).map(|sink| logger.add_sink(sink)),
+            };
+            if let Err(error) = opened {
+                let _ = writeln!(io::stderr(), "cannot log into {}: {}", path.display(), error);
+            }
+        }
+        logger
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonSink, Level, Log, LogConfig, LogFormat, Logger, MemorySink, Record};
+    use crate::halite::position::Position;
+    use crate::halite::registry::Mission;
+    use serde_json::json;
+    use std::path::PathBuf;
+    
+    #[test]
+    fn filters_by_level() {
+        let sink = MemorySink::new();
+        let mut logger = Logger::new(Level::Debug).with_sink(sink.clone());
+        logger.trace("too chatty");
+        logger.debug("turn 1");
+        logger.log("plain");
+        logger.error("lost a ship");
+        assert!(!logger.enabled(Level::Trace));
+        assert_eq!(sink.lines(), vec!["[debug] turn 1", "[info] plain", "[error] lost a ship"]);
+        assert!(!Logger::new(Level::Trace).enabled(Level::Error));
+    }
+    
+    #[test]
+    fn reads_options_from_the_command_line() {
+        let args = vec!["42", "--log-level", "TRACE", "--log-dir", "/tmp/bot", "--log-stderr"];
+        let (config, rest) = LogConfig::default().with_args(args.into_iter().map(String::from)).unwrap();
+        assert_eq!(config, LogConfig {
+            level: Level::Trace,
+            dir: Some(PathBuf::from("/tmp/bot")),
+            format: LogFormat::Text,
+            stderr: true,
+        });
+        assert_eq!(rest, vec!["42"]);
+        
+        let misspelled = vec!["--log-level", "verbose", "42"].into_iter().map(String::from);
+        assert_eq!(LogConfig::default().with_args(misspelled), Err("unknown log level `verbose`".to_string()));
+    }
+    
+    #[test]
+    fn writes_records_as_json_lines() {
+        let sink = MemorySink::new();
+        let mut logger = Logger::new(Level::Info).with_sink(sink.clone());
+        logger.set_turn(12);
+        logger.log_record(Record::new(Level::Info, "heading home")
+            .ship(3)
+            .position(Position::new(4, 5))
+            .mission(Mission::Returning));
+        logger.warn("low on halite");
+        let records = sink.records();
+        assert_eq!(records[0].to_string(), "[info] ship 3 at 4 5 Returning: heading home");
+        assert_eq!(records[0].to_json(), json!({
+            "level": "info",
+            "turn": 12,
+            "ship_id": 3,
+            "position": { "x": 4, "y": 5 },
+            "mission": "Returning",
+            "message": "heading home",
+        }));
+        
+        let mut json = JsonSink::new(Vec::new());
+        json.write_record(&records[1]);
+        json.log("plain");
+        let text = String::from_utf8(json.writer).unwrap();
+        let lines: Vec<serde_json::Value> = text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!((lines[0]["turn"].as_u64(), lines[0]["level"].as_str()), (Some(12), Some("warn")));
+        assert_eq!((lines[1]["message"].as_str(), lines[1]["ship_id"].is_null()), (Some("plain"), true));
     }
 }
@@ -1,6 +1,22 @@
+pub mod bot;
+pub mod bots;
+pub mod command;
+pub mod constants;
+pub mod direction;
+pub mod engine;
+pub mod error;
+pub mod events;
+pub mod flog;
 pub mod game;
+pub mod grid;
 pub mod input;
 pub mod log;
+pub mod movement;
+pub mod navigation;
 pub mod position;
+pub mod registry;
+pub mod replay;
+pub mod reservation;
+pub mod transcript;
 pub mod update;
 pub mod def;
\ No newline at end of file
@@ -0,0 +1,232 @@
+//! Turns the directions every ship would like to go into moves that never put two of our ships
+//! on the same cell. Only where ships end up counts, so two ships may swap cells and a ship may
+//! follow another one into the cell it leaves.
+
+use std::collections::HashMap;
+use super::command::TurnCommands;
+use super::direction::Direction;
+use super::error::InvalidCommand;
+use super::game::{Khala, MapSize, ShipId};
+use super::position::{Position, RecursiveCellPosition};
+
+#[derive(Clone, Debug)]
+struct Request {
+    ship_id: ShipId,
+    position: Position,
+    /// best first, always ends with `Still`
+    preferences: Vec<Direction>,
+    /// `None` until `prefer` is called, then the order of the call; lower goes first
+    rank: Option<usize>,
+    /// too little cargo to pay for leaving the cell
+    stuck: bool,
+}
+
+/// Collects the wishes of every ship of the bot for one turn.
+#[derive(Clone, Debug)]
+pub struct MoveResolver {
+    map_size: MapSize,
+    shipyard: Option<Position>,
+    requests: Vec<Request>,
+    ranked: usize,
+    spawn: bool,
+}
+
+/// What `MoveResolver::resolve` settled on.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Resolution {
+    /// one move per ship, in the order of `Khala::ships`
+    pub moves: Vec<(ShipId, Direction)>,
+    /// `false` when a spawn was asked for but a ship could not get off the shipyard
+    pub spawn: bool,
+}
+
+impl MoveResolver {
+    /// Every ship of the bot stays put until told otherwise. A ship that cannot pay for leaving
+    /// its cell stays no matter what it prefers.
+    pub fn new<R, W>(khala: &Khala<R, W>) -> MoveResolver {
+        let ratio = khala.game_constants.move_cost_ratio;
+        let requests = khala.ships.iter().filter(|ship| ship.owner_id == khala.my_id).map(|ship| {
+            Request {
+                ship_id: ship.ship_id,
+                position: ship.position,
+                preferences: vec![Direction::Still],
+                rank: None,
+                stuck: ship.cargo < khala.resource_map[ship.position] / ratio,
+            }
+        }).collect();
+        MoveResolver {
+            map_size: khala.resource_map.size(),
+            shipyard: khala.shipyard_of(khala.my_id).map(|shipyard| shipyard.position),
+            requests,
+            ranked: 0,
+            spawn: false,
+        }
+    }
+    
+    /// The directions `ship_id` would like to take, best first; `Still` is tried once they are
+    /// all taken. Ships that got their wishes in earlier win contested cells. The wishes of a
+    /// ship that cannot pay for moving are ignored.
+    pub fn prefer(&mut self, ship_id: ShipId, directions: &[Direction]) -> Result<(), InvalidCommand> {
+        let ranked = self.ranked;
+        let request = self.requests
+                          .iter_mut()
+                          .find(|request| request.ship_id == ship_id)
+                          .ok_or(InvalidCommand::UnknownShip(ship_id))?;
+        if request.rank.is_some() {
+            return Err(InvalidCommand::AlreadyCommanded(ship_id));
+        }
+        request.rank = Some(ranked);
+        self.ranked += 1;
+        if request.stuck {
+            return Ok(());
+        }
+        let mut preferences: Vec<Direction> = Vec::new();
+        for &direction in directions.iter().chain(Some(&Direction::Still)) {
+            if !preferences.contains(&direction) {
+                preferences.push(direction);
+            }
+        }
+        request.preferences = preferences;
+        Ok(())
+    }
+    
+    /// Keeps the shipyard free for a new ship.
+    pub fn spawn(&mut self) {
+        self.spawn = true;
+    }
+    
+    /// Every ship takes its best direction nobody else wants more: a ship that stays keeps its
+    /// cell, otherwise the ship ranked first wins and the others try their next preference.
+    pub fn resolve(&self) -> Resolution {
+        let mut choices = vec![0; self.requests.len()];
+        let target = |index: usize, choice: usize| {
+            let request = &self.requests[index];
+            request.position.offset(request.preferences[choice], &self.map_size)
+        };
+        loop {
+            let mut claims: HashMap<Position, Vec<usize>> = HashMap::new();
+            for (index, &choice) in choices.iter().enumerate() {
+                claims.entry(target(index, choice)).or_default().push(index);
+            }
+            let mut bumped: Vec<usize> = Vec::new();
+            for (position, indexes) in claims.iter() {
+                let spawning = self.spawn && Some(*position) == self.shipyard;
+                if indexes.len() < 2 && !spawning {
+                    continue;
+                }
+                // no two ships start on the same cell, so at most one of them stays
+                let staying = indexes.iter().find(|&&index| {
+                    self.requests[index].preferences[choices[index]] == Direction::Still
+                });
+                let winner = match staying {
+                    Some(&index) => Some(index),
+                    None if spawning => None,
+                    None => indexes.iter().cloned().min_by_key(|&index| self.requests[index].rank),
+                };
+                bumped.extend(indexes.iter().filter(|&&index| Some(index) != winner));
+            }
+            if bumped.is_empty() {
+                break;
+            }
+            for index in bumped {
+                choices[index] += 1;
+            }
+        }
+        let spawn = self.spawn && self.shipyard.is_some_and(|shipyard| {
+            (0..self.requests.len()).all(|index| target(index, choices[index]) != shipyard)
+        });
+        Resolution {
+            moves: self.requests
+                       .iter()
+                       .zip(choices)
+                       .map(|(request, choice)| (request.ship_id, request.preferences[choice]))
+                       .collect(),
+            spawn,
+        }
+    }
+}
+
+impl Resolution {
+    pub fn direction_of(&self, ship_id: ShipId) -> Option<Direction> {
+        self.moves.iter().find(|&&(id, _)| id == ship_id).map(|&(_, direction)| direction)
+    }
+    
+    /// Adds every move, then the spawn if there is one, stopping at the first one refused.
+    pub fn apply_to(&self, commands: &mut TurnCommands) -> Result<(), InvalidCommand> {
+        for &(ship_id, direction) in self.moves.iter() {
+            commands.move_ship(ship_id, direction)?;
+        }
+        if self.spawn {
+            commands.spawn()?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::MoveResolver;
+    use crate::halite::direction::Direction::{self, East, North, South, Still, West};
+    use crate::halite::game::khala_from;
+    use crate::halite::update::Update;
+    
+    // a 5x3 map with the shipyard in the corner; leaving (1, 1) costs 50
+    fn resolver_for(ships: &[(u32, i32, i32, u32)]) -> MoveResolver {
+        let mut game = String::from("{}\n1 0\n0 0 0\n5 3\n0 0 0 0 0\n0 500 0 0 0\n0 0 0 0 0\n1\n");
+        game += &format!("0 {} 0 5000\n", ships.len());
+        for &(ship_id, x, y, cargo) in ships {
+            game += &format!("{} {} {} {}\n", ship_id, x, y, cargo);
+        }
+        game += "0\n";
+        let mut khala = khala_from(&game);
+        khala.update().unwrap();
+        MoveResolver::new(&khala)
+    }
+    
+    fn moves(resolver: &MoveResolver) -> Vec<Direction> {
+        resolver.resolve().moves.into_iter().map(|(_, direction)| direction).collect()
+    }
+    
+    #[test]
+    fn lets_ships_swap_and_follow_each_other() {
+        let mut resolver = resolver_for(&[(0, 1, 0, 0), (1, 2, 0, 0), (2, 3, 0, 0), (3, 3, 1, 0), (4, 4, 1, 0)]);
+        resolver.prefer(0, &[East]).unwrap();
+        resolver.prefer(1, &[West]).unwrap();
+        resolver.prefer(2, &[South]).unwrap();
+        resolver.prefer(3, &[East]).unwrap();
+        // wraps around onto (0, 1)
+        resolver.prefer(4, &[East]).unwrap();
+        assert_eq!(moves(&resolver), vec![East, West, South, East, East]);
+    }
+    
+    #[test]
+    fn falls_back_when_cells_are_taken() {
+        // ship 0 sits on the rich cell with too little cargo to leave it
+        let mut resolver = resolver_for(&[(0, 1, 1, 10), (1, 2, 1, 0), (2, 3, 2, 0), (3, 2, 0, 0)]);
+        resolver.prefer(0, &[North]).unwrap();
+        resolver.prefer(1, &[West, South]).unwrap();
+        resolver.prefer(2, &[West]).unwrap();
+        // follows ship 1 into the cell it leaves
+        resolver.prefer(3, &[South, West]).unwrap();
+        assert_eq!(moves(&resolver), vec![Still, South, Still, South]);
+        assert!(resolver.prefer(3, &[North]).is_err());
+        assert!(resolver.prefer(9, &[North]).is_err());
+    }
+    
+    #[test]
+    fn keeps_the_shipyard_free_for_a_spawn() {
+        let mut resolver = resolver_for(&[(0, 1, 0, 0), (1, 0, 0, 0)]);
+        resolver.spawn();
+        resolver.prefer(0, &[West, South]).unwrap();
+        resolver.prefer(1, &[East]).unwrap();
+        let resolution = resolver.resolve();
+        assert_eq!(resolution.direction_of(0), Some(South));
+        assert_eq!(resolution.direction_of(1), Some(East));
+        assert!(resolution.spawn);
+        
+        let mut resolver = resolver_for(&[(1, 0, 0, 0)]);
+        resolver.spawn();
+        assert!(!resolver.resolve().spawn);
+    }
+}
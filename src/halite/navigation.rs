@@ -0,0 +1,337 @@
+//! Shortest ways across the wrapping map. Leaving a cell burns `1 / MOVE_COST_RATIO` of the
+//! halite on it, so besides the number of turns every route also knows the cargo it costs.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use super::direction::Direction;
+use super::game::{HaliteAmount, Khala};
+use super::grid::Grid;
+use super::position::{Position, RecursiveCellPosition};
+
+/// What a route or a distance field makes as small as possible first; the other one breaks ties.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Metric {
+    Turns,
+    Halite,
+}
+
+/// The price of getting somewhere.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Cost {
+    pub turns: u32,
+    pub halite: HaliteAmount,
+}
+
+impl Cost {
+    fn key(self, metric: Metric) -> (u32, u32) {
+        match metric {
+            Metric::Turns => (self.turns, self.halite),
+            Metric::Halite => (self.halite, self.turns),
+        }
+    }
+    
+    /// One more move, out of a cell that burns `halite`.
+    fn step(self, halite: HaliteAmount) -> Cost {
+        Cost {
+            turns: self.turns + 1,
+            halite: self.halite + halite,
+        }
+    }
+}
+
+/// A route found by `Navigator::a_star`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Path {
+    pub directions: Vec<Direction>,
+    /// where the ship is after each move, the target last
+    pub cells: Vec<Position>,
+    pub cost: Cost,
+}
+
+impl Path {
+    /// `Still` once the ship is there.
+    pub fn first_direction(&self) -> Direction {
+        self.directions.first().cloned().unwrap_or(Direction::Still)
+    }
+}
+
+/// The move cost of every cell and the cells ships may not enter.
+#[derive(Clone, Debug)]
+pub struct Navigator {
+    move_costs: Grid<HaliteAmount>,
+    blocked: Grid<bool>,
+}
+
+impl Navigator {
+    pub fn new(resource_map: &Grid<HaliteAmount>, move_cost_ratio: u32) -> Navigator {
+        Navigator {
+            move_costs: resource_map.map(|_, &halite| halite / move_cost_ratio),
+            blocked: Grid::new(resource_map.size(), false),
+        }
+    }
+    
+    pub fn for_khala<R, W>(khala: &Khala<R, W>) -> Navigator {
+        Navigator::new(&khala.resource_map, khala.game_constants.move_cost_ratio)
+    }
+    
+    /// Halite a ship burns moving off `position`.
+    pub fn move_cost(&self, position: Position) -> HaliteAmount {
+        self.move_costs[position]
+    }
+    
+    /// Keeps routes out of `position`, e.g. for an enemy ship or a cell another ship reserved.
+    pub fn block(&mut self, position: Position) {
+        self.blocked[position] = true;
+    }
+    
+    pub fn block_all<I: IntoIterator<Item = Position>>(&mut self, positions: I) {
+        for position in positions {
+            self.block(position);
+        }
+    }
+    
+    pub fn unblock(&mut self, position: Position) {
+        self.blocked[position] = false;
+    }
+    
+    pub fn is_blocked(&self, position: Position) -> bool {
+        self.blocked[position]
+    }
+    
+    /// Turns from every cell to the closest of `sources`, the cheaper way among equally short
+    /// ones.
+    pub fn bfs(&self, sources: &[Position]) -> DistanceField {
+        let mut costs: Grid<Option<Cost>> = Grid::new(self.move_costs.size(), None);
+        let mut queue = VecDeque::new();
+        for &source in sources.iter().filter(|&&source| !self.is_blocked(source)) {
+            if costs[source].is_none() {
+                costs[source] = Some(Cost::default());
+                queue.push_back(source);
+            }
+        }
+        // every cell of one layer is expanded before the next layer, so a cell found earlier in
+        // the same layer can still get cheaper
+        while let Some(position) = queue.pop_front() {
+            let cost = costs[position].unwrap();
+            for neighbor in position.neighbors(&self.move_costs).iter().cloned() {
+                if self.is_blocked(neighbor) {
+                    continue;
+                }
+                let through = cost.step(self.move_cost(neighbor));
+                match costs[neighbor] {
+                    None => {
+                        costs[neighbor] = Some(through);
+                        queue.push_back(neighbor);
+                    }
+                    Some(known) if known.turns == through.turns && through.halite < known.halite => {
+                        costs[neighbor] = Some(through);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        DistanceField { metric: Metric::Turns, costs }
+    }
+    
+    /// The least halite burnt from every cell to any of `sources`, e.g. all friendly structures
+    /// for ships on the way back.
+    pub fn dijkstra(&self, sources: &[Position]) -> DistanceField {
+        let metric = Metric::Halite;
+        let mut costs: Grid<Option<Cost>> = Grid::new(self.move_costs.size(), None);
+        let mut heap = BinaryHeap::new();
+        for &source in sources.iter().filter(|&&source| !self.is_blocked(source)) {
+            costs[source] = Some(Cost::default());
+            heap.push(Reverse((Cost::default().key(metric), source)));
+        }
+        while let Some(Reverse((key, position))) = heap.pop() {
+            let cost = costs[position].unwrap();
+            if cost.key(metric) != key {
+                continue;
+            }
+            // searching backwards, so the cost is the one of leaving the neighbor
+            for neighbor in position.neighbors(&self.move_costs).iter().cloned() {
+                if self.is_blocked(neighbor) {
+                    continue;
+                }
+                let through = cost.step(self.move_cost(neighbor));
+                if costs[neighbor].is_none_or(|known| through.key(metric) < known.key(metric)) {
+                    costs[neighbor] = Some(through);
+                    heap.push(Reverse((through.key(metric), neighbor)));
+                }
+            }
+        }
+        DistanceField { metric, costs }
+    }
+    
+    /// The best route from `from` to `to`, `None` when blocked cells cut `to` off. `from` itself
+    /// may be blocked, a ship is allowed to leave its own cell.
+    pub fn a_star(&self, from: Position, to: Position, metric: Metric) -> Option<Path> {
+        let size = self.move_costs.size();
+        let (from, to) = (self.move_costs.wrap(from), self.move_costs.wrap(to));
+        if from != to && self.is_blocked(to) {
+            return None;
+        }
+        // the halite part of the estimate is 0, cells can be empty all the way
+        let estimate = |position: Position, cost: Cost| {
+            let turns = position.distance(&to, &size);
+            match metric {
+                Metric::Turns => (cost.turns + turns, cost.halite),
+                Metric::Halite => (cost.halite, cost.turns + turns),
+            }
+        };
+        let mut costs: Grid<Option<Cost>> = Grid::new(size, None);
+        let mut came_from: Grid<Option<Direction>> = Grid::new(size, None);
+        let mut heap = BinaryHeap::new();
+        costs[from] = Some(Cost::default());
+        heap.push(Reverse((estimate(from, Cost::default()), from)));
+        while let Some(Reverse((key, position))) = heap.pop() {
+            let cost = costs[position].unwrap();
+            if key != estimate(position, cost) {
+                continue;
+            }
+            if position == to {
+                return Some(self.trace_back(from, to, cost, &came_from));
+            }
+            for &direction in Direction::ALL_CARDINALS.iter() {
+                let neighbor = position.offset(direction, &size);
+                if self.is_blocked(neighbor) {
+                    continue;
+                }
+                let through = cost.step(self.move_cost(position));
+                if costs[neighbor].is_none_or(|known| through.key(metric) < known.key(metric)) {
+                    costs[neighbor] = Some(through);
+                    came_from[neighbor] = Some(direction);
+                    heap.push(Reverse((estimate(neighbor, through), neighbor)));
+                }
+            }
+        }
+        None
+    }
+    
+    fn trace_back(&self, from: Position, to: Position, cost: Cost,
+                  came_from: &Grid<Option<Direction>>) -> Path {
+        let size = self.move_costs.size();
+        let mut directions = Vec::new();
+        let mut cells = Vec::new();
+        let mut position = to;
+        while position != from {
+            let direction = came_from[position].unwrap();
+            directions.push(direction);
+            cells.push(position);
+            position = position.offset(direction.invert(), &size);
+        }
+        directions.reverse();
+        cells.reverse();
+        Path { directions, cells, cost }
+    }
+}
+
+/// The cost from every cell to the closest source, see `Navigator::bfs` and
+/// `Navigator::dijkstra`.
+#[derive(Clone, Debug)]
+pub struct DistanceField {
+    metric: Metric,
+    costs: Grid<Option<Cost>>,
+}
+
+impl DistanceField {
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+    
+    /// `None` for blocked cells and cells the sources cannot be reached from.
+    pub fn cost(&self, position: Position) -> Option<Cost> {
+        self.costs[position]
+    }
+    
+    /// The first move of the best way to a source, `Still` on a source and `None` when no
+    /// neighbor leads to one. Works from blocked cells too, as long as a neighbor is reachable.
+    pub fn direction_from(&self, position: Position) -> Option<Direction> {
+        if self.cost(position).is_some_and(|cost| cost.turns == 0) {
+            return Some(Direction::Still);
+        }
+        let metric = self.metric;
+        Direction::ALL_CARDINALS
+            .iter()
+            .filter_map(|&direction| {
+                let neighbor = position.offset(direction, &self.costs);
+                self.cost(neighbor).map(|cost| (cost.key(metric), direction))
+            })
+            .min()
+            .map(|(_, direction)| direction)
+    }
+    
+    /// Every move from `position` to the closest source, `None` when there is none.
+    pub fn path_from(&self, position: Position) -> Option<Vec<Direction>> {
+        let mut directions = Vec::new();
+        let mut position = position;
+        loop {
+            match self.direction_from(position)? {
+                Direction::Still => return Some(directions),
+                direction => {
+                    directions.push(direction);
+                    position = position.offset(direction, &self.costs);
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Metric, Navigator};
+    use crate::halite::direction::Direction;
+    use crate::halite::grid::Grid;
+    use crate::halite::position::Position;
+    
+    // leaving the rich cell burns 100 halite, every other cell is free
+    fn navigator() -> Navigator {
+        let halite = Grid::from_rows(vec![
+            vec![0, 0, 0, 0, 0, 0],
+            vec![0, 1000, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0, 0],
+        ]).unwrap();
+        Navigator::new(&halite, 10)
+    }
+    
+    #[test]
+    fn a_star_trades_turns_for_halite() {
+        let navigator = navigator();
+        let (from, to) = (Position::new(1, 1), Position::new(3, 1));
+        let fast = navigator.a_star(from, to, Metric::Turns).unwrap();
+        assert_eq!(fast.cost.turns, 2);
+        assert_eq!(fast.cost.halite, 100);
+        assert_eq!(fast.cells.last(), Some(&to));
+        
+        let mut navigator = navigator;
+        navigator.block(Position::new(2, 1));
+        let around = navigator.a_star(Position::new(0, 1), to, Metric::Halite).unwrap();
+        assert_eq!(around.cost.halite, 0);
+        assert_eq!(around.cost.turns, 3);
+        navigator.block_all(vec![Position::new(3, 0), Position::new(3, 2), Position::new(4, 1)]);
+        assert_eq!(navigator.a_star(Position::new(0, 1), to, Metric::Halite), None);
+        assert!(navigator.a_star(to, to, Metric::Turns).unwrap().directions.is_empty());
+    }
+    
+    #[test]
+    fn distance_fields_lead_to_the_closest_source() {
+        let mut navigator = navigator();
+        let sources = [Position::new(0, 0), Position::new(4, 2)];
+        let turns = navigator.bfs(&sources);
+        assert_eq!(turns.cost(Position::new(5, 1)).unwrap().turns, 2);
+        assert_eq!(turns.cost(Position::new(2, 1)).unwrap().turns, 3);
+        assert_eq!(turns.direction_from(Position::new(4, 2)), Some(Direction::Still));
+        
+        // the ship on the rich cell pays 100 whichever way it goes, so it takes the short one
+        let halite = navigator.dijkstra(&sources);
+        assert_eq!(halite.cost(Position::new(1, 1)).unwrap().halite, 100);
+        assert_eq!(halite.path_from(Position::new(1, 1)).unwrap().len(), 2);
+        
+        navigator.block_all(vec![Position::new(1, 0), Position::new(0, 1), Position::new(5, 0),
+                                 Position::new(0, 2)]);
+        let walled = navigator.dijkstra(&[Position::new(0, 0)]);
+        assert_eq!(walled.cost(Position::new(3, 1)), None);
+        assert_eq!(walled.direction_from(Position::new(1, 0)), Some(Direction::West));
+    }
+}
@@ -1,97 +1,212 @@
-pub type Position = (u32, u32);
+use std::fmt;
+use std::ops::{Add, Sub};
+use super::direction::Direction;
+
+/// A cell of the map. Coordinates are signed so that stepping off an edge, e.g. `position +
+/// Direction::West` at `x == 0`, is well defined; `normalize` brings such a position back into
+/// the map, and `Grid` wraps it on its own.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Default)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn new(x: i32, y: i32) -> Position {
+        Position { x, y }
+    }
+}
+
+impl From<(u32, u32)> for Position {
+    fn from((x, y): (u32, u32)) -> Position {
+        Position::new(x as i32, y as i32)
+    }
+}
+
+impl From<(i32, i32)> for Position {
+    fn from((x, y): (i32, i32)) -> Position {
+        Position::new(x, y)
+    }
+}
+
+/// One step, without wrapping.
+impl Add<Direction> for Position {
+    type Output = Position;
+    
+    fn add(self, direction: Direction) -> Position {
+        let (dx, dy) = direction.delta();
+        Position::new(self.x + dx, self.y + dy)
+    }
+}
+
+/// The plain `(dx, dy)` between two positions, see `offset_to` for the shortest one across the
+/// wrap.
+impl Sub for Position {
+    type Output = (i32, i32);
+    
+    fn sub(self, other: Position) -> (i32, i32) {
+        (self.x - other.x, self.y - other.y)
+    }
+}
+
+/// The engine's `x y` format.
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.x, self.y)
+    }
+}
 
 pub trait SizedGrid2D {
     fn get_size(&self) -> (u32, u32);
 }
 
+/// A bare `(width, height)` is the smallest thing positions can wrap around.
+impl SizedGrid2D for (u32, u32) {
+    fn get_size(&self) -> (u32, u32) {
+        *self
+    }
+}
+
 pub trait RecursiveCellPosition {
-    fn get_x(&self) -> u32;
-    fn get_y(&self) -> u32;
+    fn get_x(&self) -> i32;
+    fn get_y(&self) -> i32;
     fn north<M: SizedGrid2D>(&self, recursive_map: &M) -> Self;
     fn south<M: SizedGrid2D>(&self, recursive_map: &M) -> Self;
     fn east<M: SizedGrid2D>(&self, recursive_map: &M) -> Self;
     fn west<M: SizedGrid2D>(&self, recursive_map: &M) -> Self;
+    fn offset<M: SizedGrid2D>(&self, direction: Direction, recursive_map: &M) -> Self;
+    /// The same cell with both coordinates inside the map.
+    fn normalize<M: SizedGrid2D>(&self, recursive_map: &M) -> Self;
+    /// The shortest `(dx, dy)` leading from `self` to `target` across the wrap; east and south
+    /// are positive. Halfway around the map the positive way is picked.
+    fn offset_to<M: SizedGrid2D>(&self, target: &Self, recursive_map: &M) -> (i32, i32);
+    /// Manhattan distance across the wrap, i.e. the number of moves between the two cells.
+    fn distance<M: SizedGrid2D>(&self, target: &Self, recursive_map: &M) -> u32;
+    /// The one or two cardinals that bring a ship closer to `target`, the one along the longer
+    /// axis first; empty once there.
+    fn directions_toward<M: SizedGrid2D>(&self, target: &Self, recursive_map: &M) -> Vec<Direction>;
+    /// The four cells one move away, in `Direction::ALL_CARDINALS` order.
+    fn neighbors<M: SizedGrid2D>(&self, recursive_map: &M) -> [Self; 4] where Self: Sized;
+}
+
+/// Shortest signed way from `from` to `to` on a ring of `size` cells.
+fn wrapped_delta(from: i32, to: i32, size: u32) -> i32 {
+    let size = size as i32;
+    let forward = (to - from).rem_euclid(size);
+    if forward * 2 <= size {
+        forward
+    } else {
+        forward - size
+    }
 }
 
 impl RecursiveCellPosition for Position {
-    fn get_x(&self) -> u32 {
-        return self.0;
+    fn get_x(&self) -> i32 {
+        self.x
     }
-    fn get_y(&self) -> u32 {
-        return self.1;
+    fn get_y(&self) -> i32 {
+        self.y
     }
     fn north<M: SizedGrid2D>(&self, recursive_map: &M) -> Position {
-        let position = (self.get_x(), self.get_y());
-        match position {
-            (x, y) => {
-                if y <= 0 {
-                    (x, recursive_map.get_size().1 - 1)
-                } else {
-                    (x, y - 1)
-                }
-            }
-        }
+        (*self + Direction::North).normalize(recursive_map)
     }
     fn south<M: SizedGrid2D>(&self, recursive_map: &M) -> Position {
-        let position = (self.get_x(), self.get_y());
-        match position {
-            (x, y) => {
-                if (y + 1) > (recursive_map.get_size().1 - 1) {
-                    (x, 0)
-                } else {
-                    (x, y + 1)
-                }
-            }
-        }
+        (*self + Direction::South).normalize(recursive_map)
     }
     fn east<M: SizedGrid2D>(&self, recursive_map: &M) -> Position {
-        let position = (self.get_x(), self.get_y());
-        match position {
-            (x, y) => {
-                if (x + 1) > (recursive_map.get_size().0 - 1) {
-                    (0, y)
-                } else {
-                    (x + 1, y)
-                }
-            }
-        }
+        (*self + Direction::East).normalize(recursive_map)
     }
     fn west<M: SizedGrid2D>(&self, recursive_map: &M) -> Position {
-        let position = (self.get_x(), self.get_y());
-        match position {
-            (x, y) => {
-                if x <= 0 {
-                    (recursive_map.get_size().0 - 1, y)
-                } else {
-                    (x - 1, y)
-                }
-            }
+        (*self + Direction::West).normalize(recursive_map)
+    }
+    fn offset<M: SizedGrid2D>(&self, direction: Direction, recursive_map: &M) -> Position {
+        (*self + direction).normalize(recursive_map)
+    }
+    fn normalize<M: SizedGrid2D>(&self, recursive_map: &M) -> Position {
+        let (width, height) = recursive_map.get_size();
+        Position::new(self.x.rem_euclid(width as i32), self.y.rem_euclid(height as i32))
+    }
+    fn offset_to<M: SizedGrid2D>(&self, target: &Position, recursive_map: &M) -> (i32, i32) {
+        let (width, height) = recursive_map.get_size();
+        (wrapped_delta(self.x, target.x, width), wrapped_delta(self.y, target.y, height))
+    }
+    fn distance<M: SizedGrid2D>(&self, target: &Position, recursive_map: &M) -> u32 {
+        let (dx, dy) = self.offset_to(target, recursive_map);
+        dx.unsigned_abs() + dy.unsigned_abs()
+    }
+    fn directions_toward<M: SizedGrid2D>(&self, target: &Position, recursive_map: &M) -> Vec<Direction> {
+        let (dx, dy) = self.offset_to(target, recursive_map);
+        let horizontal = match dx {
+            dx if dx > 0 => Some(Direction::East),
+            dx if dx < 0 => Some(Direction::West),
+            _ => None,
+        };
+        let vertical = match dy {
+            dy if dy > 0 => Some(Direction::South),
+            dy if dy < 0 => Some(Direction::North),
+            _ => None,
+        };
+        if dy.abs() > dx.abs() {
+            vertical.into_iter().chain(horizontal).collect()
+        } else {
+            horizontal.into_iter().chain(vertical).collect()
         }
     }
+    fn neighbors<M: SizedGrid2D>(&self, recursive_map: &M) -> [Position; 4] {
+        [self.north(recursive_map), self.east(recursive_map),
+            self.south(recursive_map), self.west(recursive_map)]
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use super::SizedGrid2D;
+    use super::{Position, RecursiveCellPosition};
+    use crate::halite::direction::Direction;
+    use std::collections::BTreeSet;
     
     type SizedMap = (u32, u32);
     
-    impl SizedGrid2D for SizedMap {
-        fn get_size(&self) -> (u32, u32) {
-            self.clone()
+    #[test]
+    /// positions used to be a bare tuple, which still converts
+    fn converts_from_tuples() {
+        let map: SizedMap = (3, 3);
+        let position: Position = (1u32, 1u32).into();
+        assert_eq!(position.north(&map), Position::new(1, 0));
+        assert_eq!(position.to_string(), "1 1");
+        assert_eq!(position + Direction::West + Direction::West, Position::new(-1, 1));
+        assert_eq!(Position::new(4, 0) - position, (3, -1));
+        let ordered: BTreeSet<Position> = vec![Position::new(2, 0), Position::new(0, 2)].into_iter().collect();
+        assert_eq!(ordered.iter().next(), Some(&Position::new(0, 2)));
+    }
+    
+    #[test]
+    fn offsets_wrap_around_the_map() {
+        let map: SizedMap = (3, 4);
+        let corner = Position::new(0, 0);
+        assert_eq!(corner.offset(Direction::North, &map), Position::new(0, 3));
+        assert_eq!(corner.offset(Direction::West, &map), Position::new(2, 0));
+        assert_eq!(corner.offset(Direction::Still, &map), corner);
+        for &direction in Direction::ALL_CARDINALS.iter() {
+            assert_eq!(corner.offset(direction, &map).offset(direction.invert(), &map), corner);
         }
     }
     
     #[test]
-    /// trying to confirm that trait associated with a type alias not only works with the alias,
-    /// but it works with origin type as well
-    fn type_alias_compile_test() {
-        use super::{Position, RecursiveCellPosition};
-        let map: SizedMap = (3, 3);
-        let position: Position = (1, 1);
-        let north = position.north(&map);
-        let not_position: (u32, u32) = (1 as u32, 1 as u32);
-        let west = not_position.north(&map);
+    fn measures_across_the_wrap() {
+        let map: SizedMap = (8, 6);
+        let position = Position::new(1, 1);
+        assert_eq!(Position::new(9, -5).normalize(&map), position);
+        assert_eq!(position.offset_to(&Position::new(7, 1), &map), (-2, 0));
+        assert_eq!(position.offset_to(&Position::new(5, 4), &map), (4, 3));
+        assert_eq!(position.distance(&Position::new(7, 5), &map), 4);
+        assert_eq!(position.directions_toward(&Position::new(7, 4), &map),
+                   vec![Direction::South, Direction::West]);
+        assert_eq!(position.directions_toward(&Position::new(6, 1), &map), vec![Direction::West]);
+        assert!(position.directions_toward(&position, &map).is_empty());
+        for (&neighbor, &direction) in position.neighbors(&map).iter().zip(Direction::ALL_CARDINALS.iter()) {
+            assert_eq!(position.distance(&neighbor, &map), 1);
+            assert_eq!(position.directions_toward(&neighbor, &map), vec![direction]);
+        }
     }
 }
@@ -0,0 +1,194 @@
+//! What a bot remembers about each of its ships from one turn to the next. `Khala::update` starts
+//! the ship list over every turn, a `ShipRegistry` synced after it keeps its records by ship id.
+
+use std::collections::btree_map::{self, BTreeMap};
+use super::game::{Khala, Ship, ShipId};
+use super::position::Position;
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Mission {
+    Exploring,
+    Mining,
+    Returning,
+    /// on its way to become a dropoff
+    Constructing,
+    /// heading home for the last turns of the game
+    EndgameRecall,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShipRecord<T> {
+    /// the ship as of the last sync
+    pub ship: Ship,
+    pub mission: Mission,
+    pub target: Option<Position>,
+    /// syncs since the mission was set, 0 on the turn it was set
+    pub mission_age: u32,
+    /// the turn the ship was first seen
+    pub first_seen: u32,
+    /// anything else the bot wants to keep per ship
+    pub payload: T,
+}
+
+impl<T> ShipRecord<T> {
+    pub fn ship_id(&self) -> ShipId {
+        self.ship.ship_id
+    }
+    
+    /// Starts a new mission; setting the one the ship already has keeps its age.
+    pub fn set_mission(&mut self, mission: Mission, target: Option<Position>) {
+        if mission != self.mission {
+            self.mission_age = 0;
+        }
+        self.mission = mission;
+        self.target = target;
+    }
+}
+
+/// Called by `ShipRegistry::sync`; both do nothing unless overridden.
+pub trait RegistryHooks<T> {
+    /// A ship of the bot showed up for the first time, it starts out `Exploring` without target.
+    fn ship_created(&mut self, _record: &mut ShipRecord<T>) {}
+    /// A ship is gone, sunk or turned into a dropoff.
+    fn ship_destroyed(&mut self, _record: ShipRecord<T>) {}
+}
+
+impl<T> RegistryHooks<T> for () {}
+
+/// The records of the bot's own ships, keyed and iterated by ship id.
+#[derive(Clone, Debug)]
+pub struct ShipRegistry<T = ()> {
+    records: BTreeMap<ShipId, ShipRecord<T>>,
+}
+
+impl<T> Default for ShipRegistry<T> {
+    fn default() -> ShipRegistry<T> {
+        ShipRegistry { records: BTreeMap::new() }
+    }
+}
+
+impl<T: Default> ShipRegistry<T> {
+    pub fn new() -> ShipRegistry<T> {
+        ShipRegistry::default()
+    }
+    
+    /// Brings the records up to the turn `khala` was just updated to: new ships get a record,
+    /// known ones their new state and one more turn on their mission, missing ones are dropped.
+    pub fn sync<R, W, H: RegistryHooks<T>>(&mut self, khala: &Khala<R, W>, hooks: &mut H) {
+        let mut previous = std::mem::take(&mut self.records);
+        for ship in khala.ships.iter().filter(|ship| ship.owner_id == khala.my_id) {
+            let record = match previous.remove(&ship.ship_id) {
+                Some(mut record) => {
+                    record.ship = *ship;
+                    record.mission_age += 1;
+                    record
+                }
+                None => {
+                    let mut record = ShipRecord {
+                        ship: *ship,
+                        mission: Mission::Exploring,
+                        target: None,
+                        mission_age: 0,
+                        first_seen: khala.turn_number,
+                        payload: T::default(),
+                    };
+                    hooks.ship_created(&mut record);
+                    record
+                }
+            };
+            self.records.insert(ship.ship_id, record);
+        }
+        for (_, record) in previous {
+            hooks.ship_destroyed(record);
+        }
+    }
+}
+
+impl<T> ShipRegistry<T> {
+    pub fn get(&self, ship_id: ShipId) -> Option<&ShipRecord<T>> {
+        self.records.get(&ship_id)
+    }
+    
+    pub fn get_mut(&mut self, ship_id: ShipId) -> Option<&mut ShipRecord<T>> {
+        self.records.get_mut(&ship_id)
+    }
+    
+    pub fn contains(&self, ship_id: ShipId) -> bool {
+        self.records.contains_key(&ship_id)
+    }
+    
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+    
+    pub fn iter(&self) -> btree_map::Values<'_, ShipId, ShipRecord<T>> {
+        self.records.values()
+    }
+    
+    pub fn iter_mut(&mut self) -> btree_map::ValuesMut<'_, ShipId, ShipRecord<T>> {
+        self.records.values_mut()
+    }
+    
+    pub fn with_mission(&self, mission: Mission) -> impl Iterator<Item = &ShipRecord<T>> {
+        self.iter().filter(move |record| record.mission == mission)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Mission, RegistryHooks, ShipRecord, ShipRegistry};
+    use crate::halite::game::{khala_from, ShipId};
+    use crate::halite::position::Position;
+    use crate::halite::update::Update;
+    
+    // ship 0 sinks after turn 1, ship 2 is spawned on turn 2
+    const GAME: &str = "{}\n1 0\n0 0 0\n2 2\n0 0\n0 0\n\
+                        1\n0 2 0 5000\n0 1 0 0\n1 0 1 0\n0\n\
+                        2\n0 2 0 4000\n1 1 1 10\n2 0 0 0\n0\n";
+    
+    #[derive(Default)]
+    struct Seen {
+        created: Vec<ShipId>,
+        destroyed: Vec<(ShipId, Mission)>,
+    }
+    
+    impl RegistryHooks<u32> for Seen {
+        fn ship_created(&mut self, record: &mut ShipRecord<u32>) {
+            self.created.push(record.ship_id());
+            record.payload = 100 + record.ship_id();
+        }
+        
+        fn ship_destroyed(&mut self, record: ShipRecord<u32>) {
+            self.destroyed.push((record.ship_id(), record.mission));
+        }
+    }
+    
+    #[test]
+    fn keeps_records_across_updates() {
+        let mut khala = khala_from(GAME);
+        let mut registry: ShipRegistry<u32> = ShipRegistry::new();
+        let mut seen = Seen::default();
+        
+        khala.update().unwrap();
+        registry.sync(&khala, &mut seen);
+        assert_eq!(seen.created, vec![0, 1]);
+        registry.get_mut(0).unwrap().set_mission(Mission::Returning, Some(Position::new(0, 0)));
+        registry.get_mut(1).unwrap().set_mission(Mission::Mining, Some(Position::new(1, 1)));
+        
+        khala.update().unwrap();
+        registry.sync(&khala, &mut seen);
+        assert_eq!(seen.created, vec![0, 1, 2]);
+        assert_eq!(seen.destroyed, vec![(0, Mission::Returning)]);
+        let miner = registry.get(1).unwrap();
+        assert_eq!((miner.mission, miner.mission_age, miner.first_seen), (Mission::Mining, 1, 1));
+        assert_eq!((miner.ship.cargo, miner.payload), (10, 101));
+        assert_eq!(registry.with_mission(Mission::Exploring).map(|record| record.ship_id()).collect::<Vec<_>>(),
+                   vec![2]);
+        assert!(!registry.contains(0));
+    }
+}
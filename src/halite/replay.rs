@@ -0,0 +1,667 @@
+//! Reads and writes replays in the format of the official engine: a zstd compressed JSON
+//! document with the initial map and one frame per turn.
+//!
+//! Frame `0` only holds the initial energy. Frame `t` holds the ships as the bots saw them at
+//! the start of turn `t`, the commands they sent, and what the turn changed: events, cells,
+//! energy and deposited halite.
+
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use super::constants::Constants;
+use super::direction::Direction;
+use super::command::Command;
+use super::engine::{CommandError, Engine, GameEvent, PlayerResult};
+use super::error::ReplayError;
+use super::game::{CommandString, HaliteAmount, MapSize, PlayerId, Ship, ShipId, Structure,
+                  StructureType};
+use super::grid::Grid;
+use super::position::Position;
+
+pub const REPLAY_FILE_VERSION: u32 = 3;
+const ENGINE_VERSION: &str = "archon";
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayPlayer {
+    pub player_id: PlayerId,
+    pub name: String,
+    pub shipyard: Position,
+    /// final rank, when the replay has game statistics
+    pub rank: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    /// ships at the start of the turn
+    pub ships: Vec<Ship>,
+    pub inspired: BTreeSet<ShipId>,
+    /// `moves[player_id]` are the commands that player sent this turn
+    pub moves: Vec<Vec<Command>>,
+    pub events: Vec<GameEvent>,
+    /// cells whose halite changed during the turn, with their new amount
+    pub cells: Vec<(Position, HaliteAmount)>,
+    /// `energy[player_id]` at the end of the turn
+    pub energy: Vec<HaliteAmount>,
+    /// `deposited[player_id]` over the whole game, at the end of the turn
+    pub deposited: Vec<HaliteAmount>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub engine_version: String,
+    pub constants: Constants,
+    pub map_size: MapSize,
+    pub seed: u64,
+    pub players: Vec<ReplayPlayer>,
+    /// halite per cell before the first turn
+    pub production_map: Grid<HaliteAmount>,
+    pub frames: Vec<Frame>,
+}
+
+/// The game as a bot saw it at the start of one turn, with the same fields `Khala` has.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub turn_number: u32,
+    pub map_size: MapSize,
+    pub resource_map: Grid<HaliteAmount>,
+    pub ships: Vec<Ship>,
+    pub structures: Vec<Structure>,
+    /// indexed by player id
+    pub player_owned_halite: Vec<HaliteAmount>,
+}
+
+impl Snapshot {
+    pub fn halite_at(&self, position: Position) -> HaliteAmount {
+        self.resource_map[position]
+    }
+    
+    pub fn ships_of(&self, owner: PlayerId) -> impl Iterator<Item = &Ship> {
+        self.ships.iter().filter(move |ship| ship.owner_id == owner)
+    }
+    
+    pub fn dropoffs_of(&self, owner: PlayerId) -> impl Iterator<Item = &Structure> {
+        self.structures.iter().filter(move |structure| {
+            structure.owner_id == owner && structure.structure_type == StructureType::Dropoff
+        })
+    }
+    
+    pub fn shipyard_of(&self, owner: PlayerId) -> Option<&Structure> {
+        self.structures.iter().find(|structure| {
+            structure.owner_id == owner && structure.structure_type == StructureType::Shipyard
+        })
+    }
+}
+
+impl Replay {
+    /// Number of turns played, frame `0` does not count.
+    pub fn turns(&self) -> u32 {
+        self.frames.len().saturating_sub(1) as u32
+    }
+    
+    /// Every turn from `1` to `turns()`, in order. Cheaper than calling `snapshot` for each.
+    pub fn snapshots(&self) -> Vec<Snapshot> {
+        let mut snapshots = Vec::new();
+        self.replay_until(self.turns(), |_, snapshot| snapshots.push(snapshot()));
+        snapshots
+    }
+    
+    /// The state at the start of `turn_number`, counted from `1`.
+    pub fn snapshot(&self, turn_number: u32) -> Option<Snapshot> {
+        if turn_number == 0 || turn_number > self.turns() {
+            return None;
+        }
+        let mut last = None;
+        self.replay_until(turn_number, |turn, snapshot| if turn == turn_number {
+            last = Some(snapshot());
+        });
+        last
+    }
+    
+    /// Replays the turns from `1` to `last_turn` and hands `visit` a way to build the snapshot of
+    /// each, so that turns nobody looks at cost no copies.
+    fn replay_until<F: FnMut(u32, &dyn Fn() -> Snapshot)>(&self, last_turn: u32, mut visit: F) {
+        let mut resource_map = self.production_map.clone();
+        let mut structures: Vec<Structure> = self.players.iter().map(|player| Structure {
+            owner_id: player.player_id,
+            structure_id: 0x4000 + player.player_id,
+            position: player.shipyard,
+            structure_type: StructureType::Shipyard,
+        }).collect();
+        for (turn, pair) in self.frames.windows(2).take(last_turn as usize).enumerate() {
+            let (previous, frame) = (&pair[0], &pair[1]);
+            for &(position, halite) in previous.cells.iter() {
+                resource_map[position] = halite;
+            }
+            for event in previous.events.iter() {
+                if let GameEvent::Construct { owner_id, structure_id, position } = *event {
+                    structures.push(Structure {
+                        owner_id,
+                        structure_id,
+                        position,
+                        structure_type: StructureType::Dropoff,
+                    });
+                }
+            }
+            let turn_number = turn as u32 + 1;
+            visit(turn_number, &|| Snapshot {
+                turn_number,
+                map_size: self.map_size,
+                resource_map: resource_map.clone(),
+                ships: frame.ships.clone(),
+                structures: structures.clone(),
+                player_owned_halite: previous.energy.clone(),
+            });
+        }
+    }
+    
+    /// Reads a compressed `.hlt` file.
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Replay, ReplayError> {
+        Replay::read_from(BufReader::new(File::open(path)?))
+    }
+    
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ReplayError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+    
+    pub fn read_from<R: Read>(reader: R) -> Result<Replay, ReplayError> {
+        let bytes = zstd::decode_all(reader)?;
+        let document = serde_json::from_slice(&bytes)
+            .map_err(|error| ReplayError::BadJson(error.to_string()))?;
+        Replay::from_json(&document)
+    }
+    
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), ReplayError> {
+        let bytes = self.to_json().to_string().into_bytes();
+        zstd::stream::copy_encode(&bytes[..], writer, COMPRESSION_LEVEL)?;
+        Ok(())
+    }
+    
+    /// Decodes the uncompressed JSON document.
+    pub fn from_json(document: &Value) -> Result<Replay, ReplayError> {
+        let constants = match field(document, "", "GAME_CONSTANTS")?.as_object() {
+            Some(map) => Constants::from_json_map(map.clone())
+                .map_err(|key| bad_field(&format!("GAME_CONSTANTS.{}", key)))?,
+            None => return Err(bad_field("GAME_CONSTANTS")),
+        };
+        let engine_version = document.get("ENGINE_VERSION")
+                                     .and_then(Value::as_str)
+                                     .unwrap_or_default()
+                                     .to_string();
+        let seed = document.get("map_generator_seed").and_then(Value::as_u64).unwrap_or(constants.game_seed);
+        
+        let production = field(document, "", "production_map")?;
+        let width = integer(field(production, "production_map", "width")?, "production_map.width")?;
+        let height = integer(field(production, "production_map", "height")?, "production_map.height")?;
+        let grid = array(field(production, "production_map", "grid")?, "production_map.grid")?;
+        if grid.len() != height as usize {
+            return Err(bad_field("production_map.grid"));
+        }
+        let mut rows = Vec::new();
+        for (y, row) in grid.iter().enumerate() {
+            let path = format!("production_map.grid[{}]", y);
+            let row = array(row, &path)?;
+            if row.len() != width as usize {
+                return Err(bad_field(&path));
+            }
+            let cells: Result<Vec<HaliteAmount>, ReplayError> = row.iter().enumerate().map(|(x, cell)| {
+                let path = format!("{}[{}]", path, x);
+                integer(field(cell, &path, "energy")?, &path)
+            }).collect();
+            rows.push(cells?);
+        }
+        let production_map = Grid::from_rows(rows).ok_or_else(|| bad_field("production_map.grid"))?;
+        
+        let mut players = Vec::new();
+        for (index, player) in array(field(document, "", "players")?, "players")?.iter().enumerate() {
+            let path = format!("players[{}]", index);
+            players.push(ReplayPlayer {
+                player_id: integer(field(player, &path, "player_id")?, &path)?,
+                name: player.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+                shipyard: location(field(player, &path, "factory_location")?, &path)?,
+                rank: None,
+            });
+        }
+        players.sort_by_key(|player| player.player_id);
+        let statistics = document.get("game_statistics")
+                                 .and_then(|statistics| statistics.get("player_statistics"))
+                                 .and_then(Value::as_array);
+        for statistics in statistics.into_iter().flatten() {
+            let player_id = statistics.get("player_id").and_then(Value::as_u64);
+            let rank = statistics.get("rank").and_then(Value::as_u64);
+            if let (Some(player_id), Some(rank)) = (player_id, rank) {
+                if let Some(player) = players.iter_mut().find(|player| player.player_id as u64 == player_id) {
+                    player.rank = Some(rank as u32);
+                }
+            }
+        }
+        
+        let mut frames = Vec::new();
+        for (index, frame) in array(field(document, "", "full_frames")?, "full_frames")?.iter().enumerate() {
+            frames.push(frame_from_json(frame, &format!("full_frames[{}]", index), players.len())?);
+        }
+        
+        Ok(Replay {
+            engine_version,
+            constants,
+            map_size: (width, height),
+            seed,
+            players,
+            production_map,
+            frames,
+        })
+    }
+    
+    /// Encodes the replay as the official engine would, ready to be compressed.
+    pub fn to_json(&self) -> Value {
+        let grid: Vec<Value> = self.production_map.rows().map(|row| {
+            Value::Array(row.iter().map(|halite| json!({ "energy": halite })).collect())
+        }).collect();
+        let initial_energy = self.frames.first().map(|frame| frame.energy.clone()).unwrap_or_default();
+        let players: Vec<Value> = self.players.iter().map(|player| json!({
+            "player_id": player.player_id,
+            "name": player.name,
+            "energy": initial_energy.get(player.player_id as usize)
+                                    .cloned()
+                                    .unwrap_or(self.constants.initial_energy),
+            "entities": [],
+            "factory_location": location_json(player.shipyard),
+        })).collect();
+        let last = self.frames.last();
+        let player_statistics: Vec<Value> = self.players.iter().map(|player| json!({
+            "player_id": player.player_id,
+            "rank": player.rank,
+            "final_production": last.and_then(|frame| frame.energy.get(player.player_id as usize)),
+            "total_production": last.and_then(|frame| frame.deposited.get(player.player_id as usize)),
+        })).collect();
+        json!({
+            "ENGINE_VERSION": self.engine_version,
+            "REPLAY_FILE_VERSION": REPLAY_FILE_VERSION,
+            "GAME_CONSTANTS": Value::Object(self.constants.to_json_map()),
+            "map_generator_seed": self.seed,
+            "number_of_players": self.players.len(),
+            "players": players,
+            "production_map": {
+                "map_generator": "archon",
+                "width": self.map_size.0,
+                "height": self.map_size.1,
+                "grid": grid,
+            },
+            "full_frames": self.frames.iter().map(frame_to_json).collect::<Vec<Value>>(),
+            "game_statistics": {
+                "number_turns": self.turns(),
+                "player_statistics": player_statistics,
+            },
+        })
+    }
+}
+
+/// Records a game played with the in-crate `Engine` as it goes.
+pub struct ReplayRecorder {
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    /// Starts recording; `engine` must not have played a turn yet.
+    pub fn new(engine: &Engine) -> ReplayRecorder {
+        let players = engine.players.iter().map(|player| ReplayPlayer {
+            player_id: player.player_id,
+            name: String::new(),
+            shipyard: player.shipyard,
+            rank: None,
+        }).collect();
+        let initial = Frame {
+            energy: engine.players.iter().map(|player| player.energy).collect(),
+            moves: vec![Vec::new(); engine.players.len()],
+            deposited: vec![0; engine.players.len()],
+            ..Frame::default()
+        };
+        ReplayRecorder {
+            replay: Replay {
+                engine_version: ENGINE_VERSION.to_string(),
+                constants: engine.constants.clone(),
+                map_size: engine.map_size,
+                seed: engine.constants.game_seed,
+                players,
+                production_map: engine.resource_map.clone(),
+                frames: vec![initial],
+            },
+        }
+    }
+    
+    /// Plays one turn on `engine`, see `Engine::process_turn`, and records it. Commands of a
+    /// player who gets removed for them are left out of the replay.
+    pub fn process_turn(&mut self, engine: &mut Engine, commands: &[Vec<CommandString>]) -> Vec<CommandError> {
+        let ships: Vec<Ship> = engine.ships.values().cloned().collect();
+        let inspired = ships.iter()
+                            .map(|ship| ship.ship_id)
+                            .filter(|&ship_id| engine.is_inspired(ship_id))
+                            .collect();
+        let alive: Vec<bool> = engine.players.iter().map(|player| player.is_alive()).collect();
+        
+        let errors = engine.process_turn(commands);
+        
+        let moves = (0..engine.players.len()).map(|player_id| {
+            let kicked = errors.iter().any(|error| error.player_id as usize == player_id);
+            if !alive[player_id] || kicked {
+                return Vec::new();
+            }
+            commands.get(player_id)
+                    .and_then(|commands| Command::parse_line(&commands.join(" ")).ok())
+                    .unwrap_or_default()
+        }).collect();
+        self.replay.frames.push(Frame {
+            ships,
+            inspired,
+            moves,
+            events: engine.events().to_vec(),
+            cells: engine.changed_cells().map(|position| (position, engine.halite_at(position))).collect(),
+            energy: engine.players.iter().map(|player| player.energy).collect(),
+            deposited: engine.players.iter().map(|player| player.deposited).collect(),
+        });
+        errors
+    }
+    
+    pub fn finish(mut self, bot_names: &[String], results: &[PlayerResult]) -> Replay {
+        for player in self.replay.players.iter_mut() {
+            player.name = bot_names.get(player.player_id as usize).cloned().unwrap_or_default();
+            player.rank = results.iter()
+                                 .find(|result| result.player_id == player.player_id)
+                                 .map(|result| result.rank);
+        }
+        self.replay
+    }
+}
+
+fn bad_field(path: &str) -> ReplayError {
+    ReplayError::BadField {
+        path: path.to_string(),
+    }
+}
+
+fn field<'a>(value: &'a Value, path: &str, key: &str) -> Result<&'a Value, ReplayError> {
+    value.get(key).ok_or_else(|| if path.is_empty() {
+        bad_field(key)
+    } else {
+        bad_field(&format!("{}.{}", path, key))
+    })
+}
+
+fn integer(value: &Value, path: &str) -> Result<u32, ReplayError> {
+    value.as_u64()
+         .filter(|&number| number <= u32::MAX as u64)
+         .map(|number| number as u32)
+         .ok_or_else(|| bad_field(path))
+}
+
+fn array<'a>(value: &'a Value, path: &str) -> Result<&'a Vec<Value>, ReplayError> {
+    value.as_array().ok_or_else(|| bad_field(path))
+}
+
+fn location(value: &Value, path: &str) -> Result<Position, ReplayError> {
+    let x = integer(field(value, path, "x")?, path)?;
+    let y = integer(field(value, path, "y")?, path)?;
+    Ok(Position::from((x, y)))
+}
+
+fn location_json(position: Position) -> Value {
+    json!({ "x": position.x, "y": position.y })
+}
+
+/// Reads `{"0": ..., "1": ...}` into a vector indexed by player id.
+fn per_player<T: Clone, F>(value: Option<&Value>, path: &str, num_players: usize, default: T, read: F)
+                           -> Result<Vec<T>, ReplayError>
+    where F: Fn(&Value, &str) -> Result<T, ReplayError> {
+    let mut result = vec![default; num_players];
+    if let Some(value) = value {
+        let map = value.as_object().ok_or_else(|| bad_field(path))?;
+        for (key, value) in map.iter() {
+            let path = format!("{}.{}", path, key);
+            match key.parse::<usize>() {
+                Ok(player_id) if player_id < num_players => result[player_id] = read(value, &path)?,
+                _ => return Err(bad_field(&path)),
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn frame_from_json(frame: &Value, path: &str, num_players: usize) -> Result<Frame, ReplayError> {
+    let mut ships = Vec::new();
+    let mut inspired = BTreeSet::new();
+    let entities = per_player(frame.get("entities"), &format!("{}.entities", path), num_players,
+                              Map::new(), |value, path| {
+                                  value.as_object().cloned().ok_or_else(|| bad_field(path))
+                              })?;
+    for (owner_id, entities) in entities.iter().enumerate() {
+        for (key, entity) in entities.iter() {
+            let path = format!("{}.entities.{}.{}", path, owner_id, key);
+            let ship_id = key.parse().map_err(|_| bad_field(&path))?;
+            if entity.get("is_inspired").and_then(Value::as_bool).unwrap_or(false) {
+                inspired.insert(ship_id);
+            }
+            ships.push(Ship {
+                owner_id: owner_id as PlayerId,
+                ship_id,
+                cargo: integer(field(entity, &path, "energy")?, &path)?,
+                position: location(entity, &path)?,
+            });
+        }
+    }
+    ships.sort_by_key(|ship| ship.ship_id);
+    
+    let moves = per_player(frame.get("moves"), &format!("{}.moves", path), num_players, Vec::new(),
+                           |value, path| {
+                               array(value, path)?.iter().map(|order| order_from_json(order, path)).collect()
+                           })?;
+    
+    let mut events = Vec::new();
+    for (index, event) in frame.get("events").and_then(Value::as_array).into_iter().flatten().enumerate() {
+        let path = format!("{}.events[{}]", path, index);
+        let position = location(field(event, &path, "location")?, &path)?;
+        match event.get("type").and_then(Value::as_str) {
+            Some("spawn") => events.push(GameEvent::Spawn {
+                owner_id: integer(field(event, &path, "owner_id")?, &path)?,
+                ship_id: integer(field(event, &path, "id")?, &path)?,
+                position,
+            }),
+            Some("construct") => events.push(GameEvent::Construct {
+                owner_id: integer(field(event, &path, "owner_id")?, &path)?,
+                structure_id: integer(field(event, &path, "id")?, &path)?,
+                position,
+            }),
+            Some("shipwreck") => events.push(GameEvent::Shipwreck {
+                position,
+                ships: array(field(event, &path, "ships")?, &path)?
+                    .iter()
+                    .map(|ship_id| integer(ship_id, &path))
+                    .collect::<Result<_, _>>()?,
+            }),
+            // newer engines may add events an analysis does not need
+            _ => {}
+        }
+    }
+    
+    let mut cells = Vec::new();
+    for (index, cell) in frame.get("cells").and_then(Value::as_array).into_iter().flatten().enumerate() {
+        let path = format!("{}.cells[{}]", path, index);
+        cells.push((location(cell, &path)?, integer(field(cell, &path, "production")?, &path)?));
+    }
+    
+    Ok(Frame {
+        ships,
+        inspired,
+        moves,
+        events,
+        cells,
+        energy: per_player(frame.get("energy"), &format!("{}.energy", path), num_players, 0, integer)?,
+        deposited: per_player(frame.get("deposited"), &format!("{}.deposited", path), num_players, 0, integer)?,
+    })
+}
+
+fn order_from_json(order: &Value, path: &str) -> Result<Command, ReplayError> {
+    let ship_id = || integer(field(order, path, "id")?, path);
+    match order.get("type").and_then(Value::as_str) {
+        Some("g") => Ok(Command::Spawn),
+        Some("c") => Ok(Command::Construct(ship_id()?)),
+        Some("m") => match order.get("direction").and_then(Value::as_str).map(str::parse::<Direction>) {
+            Some(Ok(direction)) => Ok(Command::Move(ship_id()?, direction)),
+            _ => Err(bad_field(&format!("{}.direction", path))),
+        },
+        _ => Err(bad_field(&format!("{}.type", path))),
+    }
+}
+
+fn order_to_json(order: &Command) -> Value {
+    match *order {
+        Command::Spawn => json!({ "type": "g" }),
+        Command::Construct(ship_id) => json!({ "type": "c", "id": ship_id }),
+        Command::Move(ship_id, direction) => json!({ "type": "m", "id": ship_id, "direction": direction.to_string() }),
+    }
+}
+
+fn frame_to_json(frame: &Frame) -> Value {
+    let mut entities: BTreeMap<String, Map<String, Value>> = (0..frame.energy.len())
+        .map(|player_id| (player_id.to_string(), Map::new()))
+        .collect();
+    for ship in frame.ships.iter() {
+        entities.entry(ship.owner_id.to_string()).or_default().insert(ship.ship_id.to_string(), json!({
+            "x": ship.position.x,
+            "y": ship.position.y,
+            "energy": ship.cargo,
+            "is_inspired": frame.inspired.contains(&ship.ship_id),
+        }));
+    }
+    let by_player = |values: &[HaliteAmount]| -> Map<String, Value> {
+        values.iter().enumerate().map(|(player_id, value)| (player_id.to_string(), json!(value))).collect()
+    };
+    let moves: Map<String, Value> = frame.moves.iter().enumerate().map(|(player_id, orders)| {
+        (player_id.to_string(), Value::Array(orders.iter().map(order_to_json).collect()))
+    }).collect();
+    let events: Vec<Value> = frame.events.iter().map(|event| match event {
+        GameEvent::Spawn { owner_id, ship_id, position } => json!({
+            "type": "spawn",
+            "owner_id": owner_id,
+            "id": ship_id,
+            "energy": 0,
+            "location": location_json(*position),
+        }),
+        GameEvent::Construct { owner_id, structure_id, position } => json!({
+            "type": "construct",
+            "owner_id": owner_id,
+            "id": structure_id,
+            "location": location_json(*position),
+        }),
+        GameEvent::Shipwreck { position, ships } => json!({
+            "type": "shipwreck",
+            "ships": ships,
+            "location": location_json(*position),
+        }),
+    }).collect();
+    let cells: Vec<Value> = frame.cells.iter().map(|&(position, halite)| {
+        json!({ "x": position.x, "y": position.y, "production": halite })
+    }).collect();
+    json!({
+        "entities": entities,
+        "moves": moves,
+        "events": events,
+        "cells": cells,
+        "energy": by_player(&frame.energy),
+        "deposited": by_player(&frame.deposited),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Replay, ReplayRecorder};
+    use crate::halite::direction::Direction;
+    use crate::halite::engine::{Engine, GameConfig, GameEvent};
+    use crate::halite::position::Position;
+    use serde_json::json;
+    
+    #[test]
+    fn recorded_replays_reproduce_every_turn() {
+        let mut config = GameConfig::new((16, 16), 2, 3);
+        config.constants.max_turns = 20;
+        let mut engine = Engine::new(&config);
+        let mut recorder = ReplayRecorder::new(&engine);
+        let mut expected = Vec::new();
+        while !engine.is_finished() {
+            expected.push((engine.resource_map.clone(), engine.ships.values().cloned().collect::<Vec<_>>()));
+            let commands: Vec<Vec<String>> = engine.players.iter().map(|player| {
+                let mut commands: Vec<String> = engine.ships.values().filter(|ship| {
+                    ship.owner_id == player.player_id
+                }).map(|ship| {
+                    format!("m {} {}", ship.ship_id, Direction::ALL_CARDINALS[ship.ship_id as usize % 4])
+                }).collect();
+                if engine.turn_number % 3 == 1 {
+                    commands.push("g".to_string());
+                }
+                commands
+            }).collect();
+            recorder.process_turn(&mut engine, &commands);
+        }
+        let names = vec!["a".to_string(), "b".to_string()];
+        let replay = recorder.finish(&names, &engine.results());
+        
+        let mut bytes = Vec::new();
+        replay.write_to(&mut bytes).unwrap();
+        let decoded = Replay::read_from(&bytes[..]).unwrap();
+        assert_eq!(decoded, replay);
+        assert_eq!(decoded.turns(), engine.turn_number - 1);
+        assert_eq!(decoded.players[1].name, "b");
+        
+        let snapshots = decoded.snapshots();
+        assert_eq!(snapshots.len(), expected.len());
+        for (snapshot, (resource_map, ships)) in snapshots.iter().zip(expected) {
+            assert_eq!(snapshot.resource_map, resource_map);
+            assert_eq!(snapshot.ships, ships);
+        }
+        assert_eq!(decoded.snapshot(2).unwrap().ships_of(0).count(), 1);
+    }
+    
+    #[test]
+    fn decodes_official_frames() {
+        let document = json!({
+            "ENGINE_VERSION": "1.1.6",
+            "GAME_CONSTANTS": { "MAX_TURNS": 2 },
+            "map_generator_seed": 9,
+            "players": [
+                { "player_id": 0, "name": "me", "factory_location": { "x": 1, "y": 0 } },
+            ],
+            "production_map": {
+                "width": 2,
+                "height": 1,
+                "grid": [[{ "energy": 10 }, { "energy": 0 }]],
+            },
+            "full_frames": [
+                { "energy": { "0": 5000 } },
+                {
+                    "entities": { "0": { "0": { "x": 0, "y": 0, "energy": 100, "is_inspired": true } } },
+                    "moves": { "0": [{ "type": "c", "id": 0 }] },
+                    "events": [{ "type": "construct", "id": 0, "owner_id": 0, "location": { "x": 0, "y": 0 } }],
+                    "cells": [{ "x": 0, "y": 0, "production": 0 }],
+                    "energy": { "0": 2110 },
+                },
+                { "energy": { "0": 2110 } },
+            ],
+        });
+        let replay = Replay::from_json(&document).unwrap();
+        assert_eq!(replay.constants.max_turns, 2);
+        assert!(replay.frames[1].inspired.contains(&0));
+        assert!(matches!(replay.frames[1].events[0], GameEvent::Construct { structure_id: 0, .. }));
+        
+        let last = replay.snapshot(2).unwrap();
+        assert_eq!(last.halite_at(Position::new(0, 0)), 0);
+        assert_eq!(last.player_owned_halite, vec![2110]);
+        assert_eq!(last.dropoffs_of(0).count(), 1);
+        assert_eq!(last.shipyard_of(0).unwrap().position, Position::new(1, 0));
+        assert!(last.ships.is_empty());
+    }
+}
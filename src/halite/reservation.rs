@@ -0,0 +1,306 @@
+//! Cooperative paths over the next few turns, windowed hierarchical cooperative A* (WHCA*) style:
+//! ships are planned one after the other and every plan reserves the cells it goes through at
+//! the turns it is there, so ships planned later wait or go around instead of running into it.
+//! Turn 0 is the current turn, turn 1 is where a ship is after this turn's move.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use super::direction::Direction;
+use super::game::{HaliteAmount, Khala, MapSize, Ship, ShipId};
+use super::navigation::Navigator;
+use super::position::{Position, RecursiveCellPosition};
+
+/// A cell at a turn.
+type Step = (Position, u32);
+
+/// Which ship is where at each of the next `window` turns.
+#[derive(Clone, Debug)]
+pub struct ReservationTable {
+    window: u32,
+    reservations: HashMap<Step, ShipId>,
+}
+
+impl ReservationTable {
+    pub fn new(window: u32) -> ReservationTable {
+        ReservationTable {
+            window,
+            reservations: HashMap::new(),
+        }
+    }
+    
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+    
+    /// Takes `position` at `turn` for `ship_id`, or returns the ship that already has it.
+    /// Turns past the window are never reserved.
+    pub fn reserve(&mut self, position: Position, turn: u32, ship_id: ShipId) -> Result<(), ShipId> {
+        if turn > self.window {
+            return Ok(());
+        }
+        match self.reservations.get(&(position, turn)) {
+            Some(&owner) if owner != ship_id => Err(owner),
+            _ => {
+                self.reservations.insert((position, turn), ship_id);
+                Ok(())
+            }
+        }
+    }
+    
+    pub fn reserved_by(&self, position: Position, turn: u32) -> Option<ShipId> {
+        self.reservations.get(&(position, turn)).cloned()
+    }
+    
+    pub fn is_free_for(&self, position: Position, turn: u32, ship_id: ShipId) -> bool {
+        self.reserved_by(position, turn).is_none_or(|owner| owner == ship_id)
+    }
+    
+    /// Drops every reservation of `ship_id`, e.g. before planning it again.
+    pub fn release(&mut self, ship_id: ShipId) {
+        self.reservations.retain(|_, &mut owner| owner != ship_id);
+    }
+}
+
+/// A ship's moves for the next turns.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Plan {
+    pub directions: Vec<Direction>,
+    /// where the ship is after each move
+    pub cells: Vec<Position>,
+    /// `false` when the goal is further than the window or cannot be held once reached; the plan
+    /// then ends as close to it as the others allow
+    pub arrives: bool,
+    pub halite: HaliteAmount,
+    /// the turn and the ship that takes a cell of the plan first, when it cannot be held until
+    /// the end of the window; the plan is cut before that turn
+    pub conflict: Option<(u32, ShipId)>,
+}
+
+impl Plan {
+    /// `Still` for an empty plan.
+    pub fn first_direction(&self) -> Direction {
+        self.directions.first().cloned().unwrap_or(Direction::Still)
+    }
+}
+
+/// Plans ships in the order `plan` is called for them. The moves this turn are only safe among
+/// planned ships, so the first directions are best handed to a `MoveResolver` as preferences.
+#[derive(Clone, Debug)]
+pub struct CooperativePlanner {
+    map_size: MapSize,
+    navigator: Navigator,
+    table: ReservationTable,
+}
+
+impl CooperativePlanner {
+    /// Plans over the next `window` turns with the move costs of the current map.
+    pub fn new<R, W>(khala: &Khala<R, W>, window: u32) -> CooperativePlanner {
+        CooperativePlanner {
+            map_size: khala.resource_map.size(),
+            navigator: Navigator::for_khala(khala),
+            table: ReservationTable::new(window),
+        }
+    }
+    
+    /// Cells blocked here, e.g. the ones of enemy ships, are never entered.
+    pub fn navigator_mut(&mut self) -> &mut Navigator {
+        &mut self.navigator
+    }
+    
+    pub fn table(&self) -> &ReservationTable {
+        &self.table
+    }
+    
+    pub fn table_mut(&mut self) -> &mut ReservationTable {
+        &mut self.table
+    }
+    
+    /// The quickest way for `ship` to reach `goal` and stay there until the end of the window,
+    /// the cheaper one among equally quick ones, avoiding the reservations of ships planned
+    /// earlier. The plan is reserved, replacing an older plan of the same ship. `None` when every
+    /// cell the ship could be at next turn is taken.
+    pub fn plan(&mut self, ship: &Ship, goal: Position) -> Option<Plan> {
+        self.table.release(ship.ship_id);
+        let window = self.table.window();
+        let size = self.map_size;
+        let goal = goal.normalize(&size);
+        let start = (ship.position, 0);
+        let stuck = ship.cargo < self.navigator.move_cost(ship.position);
+        let holds_goal = |table: &ReservationTable, turn: u32| {
+            (turn..=window).all(|turn| table.is_free_for(goal, turn, ship.ship_id))
+        };
+        
+        // for every (cell, turn) reached: the halite burnt and where it was reached from
+        let mut reached: HashMap<Step, (HaliteAmount, Option<(Step, Direction)>)> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        reached.insert(start, (0, None));
+        heap.push(Reverse((ship.position.distance(&goal, &size), 0, 0, ship.position)));
+        let mut arrived = None;
+        let mut closest = start;
+        while let Some(Reverse((_, halite, turn, position))) = heap.pop() {
+            if reached[&(position, turn)].0 != halite {
+                continue;
+            }
+            if position == goal && holds_goal(&self.table, turn) {
+                arrived = Some((position, turn));
+                break;
+            }
+            // deeper first, then closer to the goal, then cheaper
+            let rank = |&(position, turn): &Step| {
+                (Reverse(turn), position.distance(&goal, &size), reached[&(position, turn)].0)
+            };
+            if rank(&(position, turn)) < rank(&closest) {
+                closest = (position, turn);
+            }
+            if turn == window {
+                continue;
+            }
+            for &direction in Direction::ALL.iter() {
+                if stuck && turn == 0 && direction != Direction::Still {
+                    continue;
+                }
+                let next = position.offset(direction, &size);
+                if direction != Direction::Still && self.navigator.is_blocked(next) {
+                    continue;
+                }
+                if !self.table.is_free_for(next, turn + 1, ship.ship_id) {
+                    continue;
+                }
+                let through = match direction {
+                    Direction::Still => halite,
+                    _ => halite + self.navigator.move_cost(position),
+                };
+                let key = (next, turn + 1);
+                if reached.get(&key).is_none_or(|&(known, _)| through < known) {
+                    reached.insert(key, (through, Some(((position, turn), direction))));
+                    let estimate = turn + 1 + next.distance(&goal, &size);
+                    heap.push(Reverse((estimate, through, turn + 1, next)));
+                }
+            }
+        }
+        
+        let end = arrived.unwrap_or(closest);
+        if arrived.is_none() && end == start {
+            return None;
+        }
+        let mut directions = Vec::new();
+        let mut state = end;
+        while let Some((previous, direction)) = reached[&state].1 {
+            directions.push(direction);
+            state = previous;
+        }
+        directions.reverse();
+        Some(self.reserve(ship, directions, arrived.is_some()))
+    }
+    
+    /// Reserves the cells of `directions` and the last one until the end of the window, up to the
+    /// first cell another ship already has.
+    fn reserve(&mut self, ship: &Ship, mut directions: Vec<Direction>, arrives: bool) -> Plan {
+        let mut cells = Vec::new();
+        let mut halite = 0;
+        let mut position = ship.position;
+        let mut conflict = None;
+        for (index, &direction) in directions.iter().enumerate() {
+            let next = position.offset(direction, &self.map_size);
+            if let Err(owner) = self.table.reserve(next, index as u32 + 1, ship.ship_id) {
+                conflict = Some((index as u32 + 1, owner));
+                break;
+            }
+            if direction != Direction::Still {
+                halite += self.navigator.move_cost(position);
+            }
+            position = next;
+            cells.push(position);
+        }
+        directions.truncate(cells.len());
+        if conflict.is_none() {
+            for turn in directions.len() as u32 + 1..=self.table.window() {
+                if let Err(owner) = self.table.reserve(position, turn, ship.ship_id) {
+                    conflict = Some((turn, owner));
+                    break;
+                }
+            }
+        }
+        Plan { directions, cells, arrives: arrives && conflict.is_none(), halite, conflict }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{CooperativePlanner, ReservationTable};
+    use crate::halite::direction::Direction;
+    use crate::halite::game::{khala_from, Khala};
+    use crate::halite::position::Position;
+    use crate::halite::update::Update;
+    use std::io::Cursor;
+    
+    // a 7x3 map with the shipyard at (0, 1), ships 0 and 1 east of it and ship 2 with no cargo
+    // on a cell that costs 50 to leave
+    const GAME: &str = "{}\n1 0\n0 0 1\n7 3\n0 0 0 0 0 0 0\n0 0 0 0 0 500 0\n0 0 0 0 0 0 0\n1\n\
+                        0 3 0 5000\n0 2 1 0\n1 3 1 0\n2 5 1 0\n0\n";
+    
+    fn khala() -> Khala<Cursor<Vec<u8>>, Vec<u8>> {
+        let mut khala = khala_from(GAME);
+        khala.update().unwrap();
+        khala
+    }
+    
+    #[test]
+    fn queues_behind_a_ship_holding_the_goal() {
+        let khala = khala();
+        let shipyard = Position::new(0, 1);
+        let mut planner = CooperativePlanner::new(&khala, 4);
+        // only the middle row is open
+        let walls = (0..7).flat_map(|x| vec![Position::new(x, 0), Position::new(x, 2)]);
+        planner.navigator_mut().block_all(walls);
+        
+        let first = planner.plan(&khala.ships[0], shipyard).unwrap();
+        assert!(first.arrives);
+        assert_eq!(first.directions, vec![Direction::West, Direction::West]);
+        assert_eq!(planner.table().reserved_by(shipyard, 4), Some(0));
+        
+        let second = planner.plan(&khala.ships[1], shipyard).unwrap();
+        assert!(!second.arrives);
+        assert_eq!(second.first_direction(), Direction::West);
+        assert_eq!(second.cells.last(), Some(&Position::new(1, 1)));
+        assert_eq!(second.halite, 0);
+        assert_eq!(second.conflict, None);
+    }
+    
+    #[test]
+    fn reports_a_tail_it_cannot_hold() {
+        let khala = khala();
+        let mut planner = CooperativePlanner::new(&khala, 3);
+        let walls = (0..7).flat_map(|x| vec![Position::new(x, 0), Position::new(x, 2)]);
+        planner.navigator_mut().block_all(walls);
+        // ship 2 cannot leave (5, 1) this turn, and every cell it could be at next is taken then
+        for x in 4..7 {
+            planner.table_mut().reserve(Position::new(x, 1), 2, 9).unwrap();
+        }
+        
+        let plan = planner.plan(&khala.ships[2], Position::new(6, 1)).unwrap();
+        assert!(!plan.arrives);
+        assert_eq!(plan.directions, vec![Direction::Still]);
+        assert_eq!(plan.conflict, Some((2, 9)));
+        assert_eq!(planner.table().reserved_by(Position::new(5, 1), 2), Some(9));
+        assert_eq!(planner.table().reserved_by(Position::new(5, 1), 3), None);
+    }
+    
+    #[test]
+    fn waits_for_enough_cargo_and_respects_reservations() {
+        let khala = khala();
+        let mut planner = CooperativePlanner::new(&khala, 3);
+        let plan = planner.plan(&khala.ships[2], Position::new(6, 1)).unwrap();
+        assert!(plan.arrives);
+        assert_eq!(plan.directions, vec![Direction::Still, Direction::East]);
+        assert_eq!(plan.halite, 50);
+        
+        let mut table = ReservationTable::new(2);
+        assert_eq!(table.reserve(Position::new(1, 1), 1, 7), Ok(()));
+        assert_eq!(table.reserve(Position::new(1, 1), 1, 8), Err(7));
+        assert_eq!(table.reserve(Position::new(1, 1), 3, 8), Ok(()));
+        table.release(7);
+        assert!(table.is_free_for(Position::new(1, 1), 1, 8));
+    }
+}
@@ -0,0 +1,302 @@
+//! Transcripts of a bot's session: every line the engine sent, prefixed with `< `, and every line
+//! the bot answered, prefixed with `> `, in the order they happened. A transcript captured in a
+//! real game can be played again offline to see whether the bot still answers the same.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+use super::bot::{play, Bot};
+use super::error::ProtocolError;
+use super::game::Khala;
+use super::log::LogConfig;
+
+const ENGINE_PREFIX: &str = "< ";
+const BOT_PREFIX: &str = "> ";
+
+struct Recorder {
+    writer: Box<dyn Write>,
+    /// the line of each side that is not complete yet
+    engine_line: Vec<u8>,
+    bot_line: Vec<u8>,
+}
+
+impl Recorder {
+    /// What the engine sent is flushed as soon as a line of it is complete, so the transcript
+    /// holds everything up to a line the bot fails on even when it exits without unwinding.
+    fn record(&mut self, from_engine: bool, bytes: &[u8]) {
+        let (prefix, line) = match from_engine {
+            true => (ENGINE_PREFIX, &mut self.engine_line),
+            false => (BOT_PREFIX, &mut self.bot_line),
+        };
+        let mut completed = false;
+        for &byte in bytes {
+            if byte != b'\n' {
+                line.push(byte);
+                continue;
+            }
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            // a transcript that cannot be written is no reason to lose the game
+            let _ = self.writer.write_all(prefix.as_bytes());
+            let _ = self.writer.write_all(line);
+            let _ = self.writer.write_all(b"\n");
+            line.clear();
+            completed = true;
+        }
+        if from_engine && completed {
+            let _ = self.writer.flush();
+        }
+    }
+}
+
+/// Where both sides of a session are written to. Clones write into the same transcript.
+#[derive(Clone)]
+pub struct Transcript {
+    recorder: Rc<RefCell<Recorder>>,
+}
+
+impl Transcript {
+    pub fn new<W: Write + 'static>(writer: W) -> Transcript {
+        Transcript {
+            recorder: Rc::new(RefCell::new(Recorder {
+                writer: Box::new(writer),
+                engine_line: Vec::new(),
+                bot_line: Vec::new(),
+            })),
+        }
+    }
+    
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Transcript> {
+        Ok(Transcript::new(BufWriter::new(File::create(path)?)))
+    }
+    
+    fn flush(&self) -> io::Result<()> {
+        self.recorder.borrow_mut().writer.flush()
+    }
+}
+
+impl fmt::Debug for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Transcript").finish()
+    }
+}
+
+/// Reads from the engine and records what was read.
+#[derive(Debug)]
+pub struct TeeReader<R> {
+    inner: R,
+    transcript: Transcript,
+}
+
+impl<R> TeeReader<R> {
+    pub fn new(inner: R, transcript: Transcript) -> TeeReader<R> {
+        TeeReader { inner, transcript }
+    }
+}
+
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.transcript.recorder.borrow_mut().record(true, &buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for TeeReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+    
+    fn consume(&mut self, amount: usize) {
+        if amount > 0 {
+            // the buffer is not empty, so this hands back what was just filled without reading
+            if let Ok(buffer) = self.inner.fill_buf() {
+                self.transcript.recorder.borrow_mut().record(true, &buffer[..amount]);
+            }
+        }
+        self.inner.consume(amount);
+    }
+}
+
+/// Writes to the engine and records what was written. Flushing it flushes the transcript too,
+/// which `Khala` does once per turn.
+#[derive(Debug)]
+pub struct TeeWriter<W> {
+    inner: W,
+    transcript: Transcript,
+}
+
+impl<W> TeeWriter<W> {
+    pub fn new(inner: W, transcript: Transcript) -> TeeWriter<W> {
+        TeeWriter { inner, transcript }
+    }
+}
+
+impl<W: Write> Write for TeeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.transcript.recorder.borrow_mut().record(false, &buf[..written]);
+        Ok(written)
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        self.transcript.flush()
+    }
+}
+
+/// A transcript read back: what the engine sent, and what the bot answered line by line, its name
+/// first and then the commands of each turn.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RecordedSession {
+    pub engine_input: String,
+    pub bot_lines: Vec<String>,
+}
+
+impl RecordedSession {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<RecordedSession> {
+        Ok(RecordedSession::parse(&fs::read_to_string(path)?))
+    }
+    
+    /// Lines without either prefix are ignored.
+    pub fn parse(text: &str) -> RecordedSession {
+        let mut session = RecordedSession::default();
+        for line in text.lines() {
+            if let Some(line) = line.strip_prefix(ENGINE_PREFIX) {
+                session.engine_input.push_str(line);
+                session.engine_input.push('\n');
+            } else if let Some(line) = line.strip_prefix(BOT_PREFIX) {
+                session.bot_lines.push(line.to_string());
+            }
+        }
+        session
+    }
+}
+
+/// The commands of a turn that differ between the transcript and the replay.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TurnDiff {
+    pub turn: u32,
+    /// recorded but not sent again
+    pub missing: Vec<String>,
+    /// sent again but not recorded
+    pub extra: Vec<String>,
+}
+
+impl fmt::Display for TurnDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "turn {}: missing [{}], extra [{}]", self.turn, self.missing.join(", "), self.extra.join(", "))
+    }
+}
+
+/// Plays `session` again with `bot` and returns the turns on which it answered differently. The
+/// order of commands within a turn does not matter.
+pub fn replay_session<B: Bot>(bot: &mut B, session: &RecordedSession, log_config: &LogConfig)
+                              -> Result<Vec<TurnDiff>, ProtocolError> {
+    let input = Cursor::new(session.engine_input.clone().into_bytes());
+    let mut khala = Khala::read_with_log(input, Vec::new(), log_config)?;
+    play(bot, &mut khala)?;
+    let replayed: Vec<String> = String::from_utf8_lossy(khala.writer()).lines().map(String::from).collect();
+    
+    let turns = session.bot_lines.len().max(replayed.len());
+    let mut diffs = Vec::new();
+    // the first line is the name
+    for turn in 1..turns {
+        let mut missing = split_commands(session.bot_lines.get(turn));
+        let mut extra = split_commands(replayed.get(turn));
+        missing.retain(|command| match extra.iter().position(|other| other == command) {
+            Some(index) => {
+                extra.remove(index);
+                false
+            }
+            None => true,
+        });
+        if !missing.is_empty() || !extra.is_empty() {
+            diffs.push(TurnDiff { turn: turn as u32, missing, extra });
+        }
+    }
+    Ok(diffs)
+}
+
+/// `g m 1 n c 2` into `g`, `m 1 n` and `c 2`.
+fn split_commands(line: Option<&String>) -> Vec<String> {
+    let tokens: Vec<&str> = line.map_or(Vec::new(), |line| line.split_whitespace().collect());
+    let mut commands = Vec::new();
+    let mut index = 0;
+    while index < tokens.len() {
+        let length = match tokens[index] {
+            "m" => 3,
+            "c" => 2,
+            _ => 1,
+        };
+        let end = (index + length).min(tokens.len());
+        commands.push(tokens[index..end].join(" "));
+        index = end;
+    }
+    commands
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{replay_session, RecordedSession, TeeReader, TeeWriter, Transcript, TurnDiff};
+    use crate::halite::bot::play;
+    use crate::halite::bots::{IdleBot, RandomBot};
+    use crate::halite::game::{Khala, TWO_TURNS};
+    use crate::halite::log::LogConfig;
+    use std::cell::RefCell;
+    use std::io::{self, BufRead, BufWriter, Cursor, Write};
+    use std::rc::Rc;
+    
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+    
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn captures_a_session_and_replays_it() {
+        let captured = Shared::default();
+        let transcript = Transcript::new(captured.clone());
+        let reader = TeeReader::new(Cursor::new(TWO_TURNS.as_bytes().to_vec()), transcript.clone());
+        let writer = TeeWriter::new(Vec::new(), transcript);
+        let mut khala = Khala::read_with_log(reader, writer, &LogConfig::disabled()).unwrap();
+        play(&mut RandomBot::new(1), &mut khala).unwrap();
+        
+        let text = String::from_utf8(captured.0.borrow().clone()).unwrap();
+        assert!(text.starts_with("< {}\n< 1 0\n"));
+        assert!(text.contains("> bot_0\n"));
+        let session = RecordedSession::parse(&text);
+        assert_eq!(session.engine_input, TWO_TURNS);
+        assert_eq!(session.bot_lines, vec!["bot_0", "g ", "g "]);
+        
+        let log = LogConfig::disabled();
+        assert_eq!(replay_session(&mut RandomBot::new(1), &session, &log).unwrap(), vec![]);
+        assert_eq!(replay_session(&mut IdleBot, &session, &log).unwrap()[1], TurnDiff {
+            turn: 2,
+            missing: vec!["g".to_string()],
+            extra: vec![],
+        });
+    }
+    
+    #[test]
+    fn flushes_each_line_of_the_engine() {
+        let captured = Shared::default();
+        let transcript = Transcript::new(BufWriter::new(captured.clone()));
+        let mut reader = TeeReader::new(Cursor::new(TWO_TURNS.as_bytes().to_vec()), transcript);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(captured.0.borrow().as_slice(), b"< {}\n");
+    }
+}
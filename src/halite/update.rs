@@ -1,3 +1,4 @@
 pub trait Update {
-    fn update(&mut self);
-}
\ No newline at end of file
+    type Error;
+    fn update(&mut self) -> Result<(), Self::Error>;
+}